@@ -0,0 +1,327 @@
+use std::fs;
+use std::path::Path;
+use crate::report::MnemonicReport;
+
+pub const DEFAULT_DECK_NAME: &str = "Mnembus";
+
+/// One number to memorize. Each note makes three cards, one for each CardKind.
+#[derive(Clone, Debug)]
+pub struct AnkiNote {
+    pub label: String,
+    pub number: String,
+    pub mnemonic: String,
+    // The codes of the mnemonic's words joined by "-", like "707-18". Shown on the back of the
+    // cards as a hint for how the phrase breaks down.
+    pub segmentation: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CardKind {
+    LabelToNumber,
+    NumberToMnemonic,
+    MnemonicToNumber,
+}
+
+impl CardKind {
+    pub const ALL: [CardKind; 3] = [CardKind::LabelToNumber, CardKind::NumberToMnemonic, CardKind::MnemonicToNumber];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardKind::LabelToNumber => "Label to number",
+            CardKind::NumberToMnemonic => "Number to mnemonic",
+            CardKind::MnemonicToNumber => "Mnemonic to number",
+        }
+    }
+
+    // Anki tags can't have spaces.
+    fn tag(&self) -> &'static str {
+        match self {
+            CardKind::LabelToNumber => "mnembus::label_to_number",
+            CardKind::NumberToMnemonic => "mnembus::number_to_mnemonic",
+            CardKind::MnemonicToNumber => "mnembus::mnemonic_to_number",
+        }
+    }
+}
+
+impl AnkiNote {
+    /// A note using the best mnemonic in the report, or None if nothing was found for the number.
+    pub fn from_report(report: &MnemonicReport) -> Option<Self> {
+        report.best.first().map(|mnemonic| Self {
+            label: report.label.clone(),
+            number: report.number.clone(),
+            mnemonic: mnemonic.phrase.clone(),
+            segmentation: mnemonic.codes.join("-"),
+        })
+    }
+
+    pub fn front(&self, card_kind: CardKind) -> String {
+        match card_kind {
+            CardKind::LabelToNumber => self.label.clone(),
+            CardKind::NumberToMnemonic => self.number.clone(),
+            CardKind::MnemonicToNumber => self.mnemonic.clone(),
+        }
+    }
+
+    pub fn back(&self, card_kind: CardKind) -> String {
+        match card_kind {
+            CardKind::LabelToNumber => format!("{} ({})", self.number, self.mnemonic),
+            CardKind::NumberToMnemonic => format!("{} ({})", self.mnemonic, self.segmentation),
+            CardKind::MnemonicToNumber => format!("{} ({})", self.number, self.segmentation),
+        }
+    }
+}
+
+/// Notes for every report that has at least one mnemonic, in the same order as the reports.
+pub fn notes_from_reports(reports: &[MnemonicReport]) -> Vec<AnkiNote> {
+    reports.iter().filter_map(AnkiNote::from_report).collect()
+}
+
+/// A plain text file for Anki's File > Import using the Basic note type. Each card is its own
+/// row of front, back and a tag naming the card kind. The header lines tell Anki the separator,
+/// the deck and which column has the tags.
+pub fn format_tsv(notes: &[AnkiNote], deck_name: &str) -> String {
+    let mut tsv = format!("#separator:tab\n#html:false\n#deck:{}\n#tags column:3\n", tsv_field(deck_name));
+    for note in notes.iter() {
+        for card_kind in CardKind::ALL.iter() {
+            tsv.push_str(&format!("{}\t{}\t{}\n", tsv_field(&note.front(*card_kind)), tsv_field(&note.back(*card_kind)), card_kind.tag()));
+        }
+    }
+    tsv
+}
+
+pub fn save_tsv<P: AsRef<Path>>(notes: &[AnkiNote], deck_name: &str, path: P) -> Result<(), String> {
+    fs::write(&path, format_tsv(notes, deck_name))
+        .map_err(|e| format!("Unable to write \"{}\": {}", path.as_ref().display(), e))
+}
+
+fn tsv_field(value: &str) -> String {
+    value.replace(&['\t', '\r', '\n'][..], " ")
+}
+
+// A hash that stays the same from run to run and from one Rust version to the next, so that
+// importing a package again updates the same deck, note type and notes instead of adding copies.
+// This is 64-bit FNV-1a.
+#[cfg(feature = "apkg")]
+fn stable_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Writes an Anki package with one note type that has Label, Number, Mnemonic and Segmentation
+/// fields and a card template for each CardKind. The package is a zip file holding a SQLite
+/// collection in the schema that Anki 2.1 imports.
+#[cfg(feature = "apkg")]
+pub fn save_apkg<P: AsRef<Path>>(notes: &[AnkiNote], deck_name: &str, path: P) -> Result<(), String> {
+    use std::io::Write;
+
+    let path = path.as_ref();
+    let collection_path = std::env::temp_dir().join(format!("mnembus-{}-{}.anki2", std::process::id(), stable_hash(&path.display().to_string())));
+    let _ = fs::remove_file(&collection_path);
+    let result = write_collection(notes, deck_name, &collection_path)
+        .and_then(|_| fs::read(&collection_path).map_err(|e| e.to_string()))
+        .and_then(|collection| {
+            let file = fs::File::create(path).map_err(|e| e.to_string())?;
+            let mut zip = zip::ZipWriter::new(file);
+            let options = zip::write::FileOptions::default();
+            zip.start_file("collection.anki2", options).map_err(|e| e.to_string())?;
+            zip.write_all(&collection).map_err(|e| e.to_string())?;
+            zip.start_file("media", options).map_err(|e| e.to_string())?;
+            zip.write_all(b"{}").map_err(|e| e.to_string())?;
+            zip.finish().map_err(|e| e.to_string())?;
+            Ok(())
+        });
+    let _ = fs::remove_file(&collection_path);
+    result.map_err(|e| format!("Unable to write \"{}\": {}", path.display(), e))
+}
+
+#[cfg(feature = "apkg")]
+const COLLECTION_SCHEMA: &str = "\
+CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null, scm integer not null, ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null, models text not null, decks text not null, dconf text not null, tags text not null);
+CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null, mod integer not null, usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null, flags integer not null, data text not null);
+CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null, mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null, ivl integer not null, factor integer not null, reps integer not null, lapses integer not null, left integer not null, odue integer not null, odid integer not null, flags integer not null, data text not null);
+CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null, ease integer not null, ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null, type integer not null);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn on notes (usn);
+CREATE INDEX ix_cards_usn on cards (usn);
+CREATE INDEX ix_revlog_usn on revlog (usn);
+CREATE INDEX ix_cards_nid on cards (nid);
+CREATE INDEX ix_cards_sched on cards (did, queue, due);
+CREATE INDEX ix_revlog_cid on revlog (cid);
+CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+#[cfg(feature = "apkg")]
+const NOTE_FIELDS: [&str; 4] = ["Label", "Number", "Mnemonic", "Segmentation"];
+
+// The question and answer templates for each CardKind, in the same order as CardKind::ALL.
+#[cfg(feature = "apkg")]
+const CARD_TEMPLATES: [(&str, &str); 3] = [
+    ("{{Label}}", "{{FrontSide}}<hr id=answer>{{Number}}<br>{{Mnemonic}}"),
+    ("{{Number}}", "{{FrontSide}}<hr id=answer>{{Mnemonic}}<br><small>{{Segmentation}}</small>"),
+    ("{{Mnemonic}}", "{{FrontSide}}<hr id=answer>{{Number}}<br><small>{{Segmentation}}</small>"),
+];
+
+#[cfg(feature = "apkg")]
+fn write_collection(notes: &[AnkiNote], deck_name: &str, collection_path: &Path) -> Result<(), String> {
+    use crate::output::json_string;
+    use rusqlite::{params, Connection};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let now_millis = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?.as_millis() as i64;
+    let now_seconds = now_millis / 1_000;
+    // Keep the IDs well inside the range that JavaScript numbers hold exactly.
+    let deck_id = 1_000_000_000 + (stable_hash(&format!("deck {}", deck_name)) % 1_000_000_000) as i64;
+    let model_id = 1_000_000_000 + (stable_hash(&format!("model {}", deck_name)) % 1_000_000_000) as i64;
+
+    let templates = CardKind::ALL.iter().zip(CARD_TEMPLATES.iter()).enumerate()
+        .map(|(ord, (card_kind, (question, answer)))| format!(
+            "{{\"name\":{},\"ord\":{},\"qfmt\":{},\"afmt\":{},\"bqfmt\":\"\",\"bafmt\":\"\",\"did\":null,\"bfont\":\"\",\"bsize\":0}}",
+            json_string(card_kind.name()), ord, json_string(question), json_string(answer)))
+        .collect::<Vec<_>>()
+        .join(",");
+    let fields = NOTE_FIELDS.iter().enumerate()
+        .map(|(ord, name)| format!("{{\"name\":{},\"ord\":{},\"sticky\":false,\"rtl\":false,\"font\":\"Arial\",\"size\":20,\"media\":[]}}", json_string(name), ord))
+        .collect::<Vec<_>>()
+        .join(",");
+    let models = format!(
+        "{{\"{id}\":{{\"id\":{id},\"name\":{name},\"type\":0,\"mod\":{mod},\"usn\":-1,\"sortf\":0,\"did\":{did},\"tmpls\":[{tmpls}],\"flds\":[{flds}],\"css\":{css},\"latexPre\":{pre},\"latexPost\":\"\\\\end{{document}}\",\"latexsvg\":false,\"tags\":[],\"vers\":[],\"req\":[[0,\"any\",[0]],[1,\"any\",[1]],[2,\"any\",[2]]]}}}}",
+        id = model_id,
+        name = json_string(&format!("{} number", deck_name)),
+        mod = now_seconds,
+        did = deck_id,
+        tmpls = templates,
+        flds = fields,
+        css = json_string(".card { font-family: arial; font-size: 24px; text-align: center; color: black; background-color: white; }"),
+        pre = json_string("\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n"));
+    let deck_json = |id: i64, name: &str| format!(
+        "\"{id}\":{{\"id\":{id},\"name\":{name},\"desc\":\"\",\"mod\":{mod},\"usn\":-1,\"collapsed\":false,\"browserCollapsed\":false,\"dyn\":0,\"conf\":1,\"extendNew\":0,\"extendRev\":0,\"newToday\":[0,0],\"revToday\":[0,0],\"lrnToday\":[0,0],\"timeToday\":[0,0]}}",
+        id = id, name = json_string(name), mod = now_seconds);
+    let decks = format!("{{{},{}}}", deck_json(1, "Default"), deck_json(deck_id, deck_name));
+    let deck_config = "{\"1\":{\"id\":1,\"name\":\"Default\",\"mod\":0,\"usn\":0,\"maxTaken\":60,\"autoplay\":true,\"timer\":0,\"replayq\":true,\"dyn\":false,\
+        \"new\":{\"bury\":true,\"delays\":[1,10],\"initialFactor\":2500,\"ints\":[1,4,7],\"order\":1,\"perDay\":20,\"separate\":true},\
+        \"lapse\":{\"delays\":[10],\"leechAction\":0,\"leechFails\":8,\"minInt\":1,\"mult\":0},\
+        \"rev\":{\"bury\":true,\"ease4\":1.3,\"fuzz\":0.05,\"ivlFct\":1,\"maxIvl\":36500,\"minSpace\":1,\"perDay\":100}}}";
+    let config = format!("{{\"activeDecks\":[1],\"curDeck\":{},\"newSpread\":0,\"collapseTime\":1200,\"timeLim\":0,\"estTimes\":true,\"dueCounts\":true,\"curModel\":{},\"nextPos\":{},\"sortType\":\"noteFld\",\"sortBackwards\":false,\"addToCur\":true}}",
+        deck_id, model_id, notes.len() + 1);
+
+    let mut connection = Connection::open(collection_path).map_err(|e| e.to_string())?;
+    let transaction = connection.transaction().map_err(|e| e.to_string())?;
+    transaction.execute_batch(COLLECTION_SCHEMA).map_err(|e| e.to_string())?;
+    transaction.execute(
+        "INSERT INTO col VALUES (1, ?, ?, ?, 11, 0, 0, 0, ?, ?, ?, ?, '{}')",
+        params![now_seconds - now_seconds % 86_400, now_millis, now_millis, config, models, decks, deck_config],
+    ).map_err(|e| e.to_string())?;
+    for (index, note) in notes.iter().enumerate() {
+        let note_id = now_millis + index as i64;
+        let fields = note_fields(note);
+        transaction.execute(
+            "INSERT INTO notes VALUES (?, ?, ?, ?, -1, ' mnembus ', ?, ?, ?, 0, '')",
+            params![note_id, format!("{:016x}", stable_hash(&format!("{}\t{}", note.label, note.number))), model_id, now_seconds, fields.join("\u{1f}"), fields[0], field_checksum(&fields[0])],
+        ).map_err(|e| e.to_string())?;
+        for ord in 0..CardKind::ALL.len() {
+            // New cards, shown in the order of the notes.
+            transaction.execute(
+                "INSERT INTO cards VALUES (?, ?, ?, ?, ?, -1, 0, 0, ?, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+                params![note_id * CardKind::ALL.len() as i64 + ord as i64, note_id, deck_id, ord as i64, now_seconds, index as i64 + 1],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    transaction.commit().map_err(|e| e.to_string())
+}
+
+// The note's fields in NOTE_FIELDS order, escaped since Anki fields are HTML. The first is the
+// sort field.
+#[cfg(feature = "apkg")]
+fn note_fields(note: &AnkiNote) -> Vec<String> {
+    [&note.label, &note.number, &note.mnemonic, &note.segmentation].iter().map(|value| escape_html(value)).collect()
+}
+
+// The checksum Anki uses to spot duplicates: the first 8 hex digits of the SHA-1 of the sort
+// field as it's stored.
+#[cfg(feature = "apkg")]
+fn field_checksum(field: &str) -> i64 {
+    i64::from_str_radix(&sha1_smol::Sha1::from(field).digest().to_string()[..8], 16).unwrap()
+}
+
+#[cfg(feature = "apkg")]
+fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::mnemonic::Mnemonic;
+    use crate::report::MnemonicReport;
+    use crate::score::ScoreBreakdown;
+    use super::{format_tsv, notes_from_reports, tsv_field, AnkiNote, CardKind};
+
+    fn note() -> AnkiNote {
+        AnkiNote {
+            label: "Mom".to_string(),
+            number: "2068909233".to_string(),
+            mnemonic: "nice chief bus pop name".to_string(),
+            segmentation: "20-68-90-92-33".to_string(),
+        }
+    }
+
+    fn report(label: &str, words: &[(&str, &str)]) -> MnemonicReport {
+        let mnemonic = words.iter().fold(Mnemonic::new(), |mnemonic, (word, code)| mnemonic.with_word(word, code, &ScoreBreakdown::default()));
+        MnemonicReport {
+            label: label.to_string(),
+            number: words.iter().map(|(_, code)| *code).collect(),
+            paths: vec![],
+            best: if words.is_empty() { vec![] } else { vec![mnemonic] },
+            elapsed: Duration::default(),
+        }
+    }
+
+    #[test]
+    fn front_and_back_for_each_card_kind() {
+        let note = note();
+        assert_eq!(note.front(CardKind::LabelToNumber), "Mom");
+        assert_eq!(note.back(CardKind::LabelToNumber), "2068909233 (nice chief bus pop name)");
+        assert_eq!(note.front(CardKind::NumberToMnemonic), "2068909233");
+        assert_eq!(note.back(CardKind::NumberToMnemonic), "nice chief bus pop name (20-68-90-92-33)");
+        assert_eq!(note.front(CardKind::MnemonicToNumber), "nice chief bus pop name");
+        assert_eq!(note.back(CardKind::MnemonicToNumber), "2068909233 (20-68-90-92-33)");
+    }
+
+    #[test]
+    fn notes_skip_reports_without_a_mnemonic() {
+        let notes = notes_from_reports(&[report("Moon", &[("executive", "70718")]), report("Nothing", &[]), report("Pin", &[("cat", "71"), ("net", "21")])]);
+        assert_eq!(notes.iter().map(|note| note.label.as_str()).collect::<Vec<_>>(), vec!["Moon", "Pin"]);
+        assert_eq!(notes[1].mnemonic, "cat net");
+        assert_eq!(notes[1].segmentation, "71-21");
+    }
+
+    #[test]
+    fn tsv_fields_have_no_separators() {
+        assert_eq!(tsv_field("a\tb\r\nc"), "a b  c");
+        assert_eq!(tsv_field("R&D <x>"), "R&D <x>");
+    }
+
+    #[test]
+    fn tsv_has_a_row_per_card() {
+        let mut note = note();
+        note.label = "Mom\tcell".to_string();
+        let tsv = format_tsv(&[note], "My\tdeck");
+        let lines = tsv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[..4], ["#separator:tab", "#html:false", "#deck:My deck", "#tags column:3"]);
+        assert_eq!(lines[4], "Mom cell\t2068909233 (nice chief bus pop name)\tmnembus::label_to_number");
+        assert_eq!(lines.len(), 4 + CardKind::ALL.len());
+        assert!(lines[4..].iter().all(|line| line.split('\t').count() == 3));
+    }
+
+    #[cfg(feature = "apkg")]
+    #[test]
+    fn checksum_is_of_the_stored_sort_field() {
+        use super::{field_checksum, note_fields};
+
+        assert_eq!(field_checksum("Mom"), 157_747_247);
+        let mut note = note();
+        note.label = "R&D".to_string();
+        let fields = note_fields(&note);
+        assert_eq!(fields[0], "R&amp;D");
+        assert_eq!(field_checksum(&fields[0]), 1_586_624_956);
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use crate::error::LoadError;
+use crate::mnemonic::{self, IndexOptions};
+use crate::report::MnemonicReport;
+use crate::score::Scorer;
+use crate::search::{SearchEngine, SearchOptions};
+use crate::words::WordList;
+
+#[derive(Clone, Debug)]
+pub struct BatchOptions {
+    // The number of worker threads. Zero means one per available CPU.
+    pub threads: usize,
+    pub search: SearchOptions,
+    // How many of the fewest-word paths to list in each report.
+    pub path_limit: usize,
+}
+
+impl BatchOptions {
+    pub fn new() -> Self {
+        Self {
+            threads: 0,
+            search: SearchOptions::new(),
+            path_limit: 10,
+        }
+    }
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads a file of "label<tab>number" lines like propose_mnemonics_path_from_file() does and
+/// searches all of them in parallel over one shared index.
+pub fn run_batch_from_file<P: AsRef<Path>>(word_list: &WordList, path: P, index_options: &IndexOptions, scorer: &dyn Scorer, options: &BatchOptions, progress: &(dyn Fn(usize, usize) + Sync)) -> Result<Vec<MnemonicReport>, LoadError> {
+    let numbers = mnemonic::load_numbers(path)?;
+    let words = mnemonic::gen_btreemap_with_options(word_list, index_options);
+    let engine = SearchEngine::new(word_list, &words, scorer);
+    Ok(run_batch(&engine, &numbers, options, progress))
+}
+
+/// Searches each (label, number) pair on a pool of worker threads that share the engine. The
+/// reports come back in the same order as the input. After each number is done, progress is
+/// called with the count done so far and the total, from whichever thread finished it.
+pub fn run_batch(engine: &SearchEngine, numbers: &[(String, String)], options: &BatchOptions, progress: &(dyn Fn(usize, usize) + Sync)) -> Vec<MnemonicReport> {
+    let thread_count = match options.threads {
+        0 => thread::available_parallelism().map_or(1, |count| count.get()),
+        threads => threads,
+    }.min(numbers.len()).max(1);
+    let next_index = AtomicUsize::new(0);
+    let done_count = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; numbers.len()]);
+    thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                loop {
+                    let index = next_index.fetch_add(1, Ordering::SeqCst);
+                    if index >= numbers.len() {
+                        break;
+                    }
+                    let (label, number) = &numbers[index];
+                    let report = engine.report(label, number, &options.search, options.path_limit);
+                    results.lock().unwrap()[index] = Some(report);
+                    progress(done_count.fetch_add(1, Ordering::SeqCst) + 1, numbers.len());
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(|result| result.unwrap()).collect()
+}
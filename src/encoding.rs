@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use crate::error::DecodeError;
+use crate::mnemonic::IndexOptions;
+use crate::scheme::{PhoneticScheme, TableScheme};
+use crate::words::{self, DecodedWord, PhraseDecoding, Pronunciation, PronunciationMap, Word, WordList};
+
+pub const ENCODING_NAME_DOMINIC: &str = "dominic";
+pub const ENCODING_NAME_NUMBER_SHAPE: &str = "number-shape";
+pub const ENCODING_NAME_NUMBER_RHYME: &str = "number-rhyme";
+
+// The Dominic System's letters. The first letters of a person's names give two digits, so Albert
+// Einstein is 15.
+const DOMINIC_TABLE: [(char, &str); 10] = [
+    ('A', "1"), ('B', "2"), ('C', "3"), ('D', "4"), ('E', "5"),
+    ('S', "6"), ('G', "7"), ('H', "8"), ('N', "9"), ('O', "0"),
+];
+
+// Words that look like the digit.
+const NUMBER_SHAPE_WORDS: [(&str, &str); 20] = [
+    ("ball", "0"), ("egg", "0"), ("candle", "1"), ("pencil", "1"), ("swan", "2"), ("duck", "2"),
+    ("handcuffs", "3"), ("heart", "3"), ("sailboat", "4"), ("flag", "4"), ("hook", "5"),
+    ("snake", "5"), ("cherry", "6"), ("club", "6"), ("boomerang", "7"), ("cliff", "7"),
+    ("snowman", "8"), ("hourglass", "8"), ("balloon", "9"), ("tadpole", "9"),
+];
+
+// Words that rhyme with the digit.
+const NUMBER_RHYME_WORDS: [(&str, &str); 20] = [
+    ("hero", "0"), ("nero", "0"), ("bun", "1"), ("sun", "1"), ("shoe", "2"), ("glue", "2"),
+    ("tree", "3"), ("bee", "3"), ("door", "4"), ("floor", "4"), ("hive", "5"), ("drive", "5"),
+    ("sticks", "6"), ("bricks", "6"), ("heaven", "7"), ("oven", "7"), ("gate", "8"),
+    ("plate", "8"), ("vine", "9"), ("wine", "9"),
+];
+
+/// Turns a word into the digits it stands for. The Major System and other phonetic schemes work
+/// from a word's pronunciation, while letter systems like the Dominic System and peg lists like
+/// number-rhyme work from its spelling. Either way the search only sees the codes on each Word.
+pub trait Encoding {
+    fn name(&self) -> &str;
+
+    /// The scheme to give each pronunciation its code, or None if codes come from spelling.
+    fn phonetic_scheme(&self) -> Option<&dyn PhoneticScheme> {
+        None
+    }
+
+    /// The digits for a word with the given phones. Phonetic encodings only look at the phones
+    /// and the others only at the spelling. An empty string means the word stands for nothing.
+    fn encode_word(&self, word: &str, phones: &[String]) -> Result<String, String>;
+
+    /// Words with fixed codes that belong in the word list even if the frequency list doesn't
+    /// have them.
+    fn fixed_words(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+}
+
+/// Digits from the letters of a word's spelling.
+#[derive(Clone, Debug)]
+pub struct LetterEncoding {
+    name: String,
+    // Uppercase.
+    letters: BTreeMap<char, String>,
+    // If true, only the first letter of each part of the word counts, so "Albert Einstein" is two
+    // digits and a word starting with a letter that isn't in the table stands for nothing.
+    // Otherwise every letter in the table counts and the rest are skipped.
+    initials_only: bool,
+}
+
+/// A fixed list of words for each digit, like the number-rhyme system's bun for 1 and shoe for 2.
+/// Words not in the list stand for nothing.
+#[derive(Clone, Debug)]
+pub struct WordPegEncoding {
+    name: String,
+    // Lowercase.
+    words: BTreeMap<String, String>,
+}
+
+impl Encoding for TableScheme {
+    fn name(&self) -> &str {
+        PhoneticScheme::name(self)
+    }
+
+    fn phonetic_scheme(&self) -> Option<&dyn PhoneticScheme> {
+        Some(self)
+    }
+
+    fn encode_word(&self, _word: &str, phones: &[String]) -> Result<String, String> {
+        if phones.is_empty() {
+            return Ok("".to_string());
+        }
+        Pronunciation::phones_to_mnemonic(self, phones)
+    }
+}
+
+impl LetterEncoding {
+    pub fn new(name: &str, initials_only: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            letters: BTreeMap::new(),
+            initials_only,
+        }
+    }
+
+    pub fn dominic() -> Self {
+        let mut encoding = Self::new(ENCODING_NAME_DOMINIC, true);
+        for (letter, digits) in DOMINIC_TABLE.iter() {
+            encoding.set(*letter, digits);
+        }
+        encoding
+    }
+
+    pub fn set(&mut self, letter: char, digits: &str) {
+        self.letters.insert(letter.to_ascii_uppercase(), digits.to_string());
+    }
+}
+
+impl Encoding for LetterEncoding {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn encode_word(&self, word: &str, _phones: &[String]) -> Result<String, String> {
+        let mut code = String::new();
+        for part in word.split(|c: char| c.is_whitespace() || c == '-').filter(|part| !part.is_empty()) {
+            let letters = part.chars().filter(|c| c.is_alphabetic()).map(|c| c.to_ascii_uppercase());
+            if self.initials_only {
+                match letters.take(1).next().and_then(|initial| self.letters.get(&initial)) {
+                    Some(digits) => code.push_str(digits),
+                    None => return Ok("".to_string()),
+                }
+            } else {
+                code.extend(letters.filter_map(|letter| self.letters.get(&letter)).map(String::as_str));
+            }
+        }
+        Ok(code)
+    }
+}
+
+impl WordPegEncoding {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            words: BTreeMap::new(),
+        }
+    }
+
+    pub fn number_shape() -> Self {
+        Self::from_table(ENCODING_NAME_NUMBER_SHAPE, &NUMBER_SHAPE_WORDS)
+    }
+
+    pub fn number_rhyme() -> Self {
+        Self::from_table(ENCODING_NAME_NUMBER_RHYME, &NUMBER_RHYME_WORDS)
+    }
+
+    pub fn set(&mut self, word: &str, digits: &str) {
+        self.words.insert(word.to_lowercase(), digits.to_string());
+    }
+
+    fn from_table(name: &str, table: &[(&str, &str)]) -> Self {
+        let mut encoding = Self::new(name);
+        for (word, digits) in table.iter() {
+            encoding.set(word, digits);
+        }
+        encoding
+    }
+}
+
+impl Encoding for WordPegEncoding {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn encode_word(&self, word: &str, _phones: &[String]) -> Result<String, String> {
+        Ok(self.words.get(&word.to_lowercase()).cloned().unwrap_or_default())
+    }
+
+    fn fixed_words(&self) -> Vec<(String, String)> {
+        self.words.iter().map(|(word, digits)| (word.clone(), digits.clone())).collect()
+    }
+}
+
+/// A built-in encoding by name, or else a phonetic scheme by name or file like
+/// TableScheme::from_name_or_path() takes.
+pub fn from_name_or_path(name_or_path: &str) -> Result<Box<dyn Encoding>, String> {
+    match name_or_path {
+        ENCODING_NAME_DOMINIC => Ok(Box::new(LetterEncoding::dominic())),
+        ENCODING_NAME_NUMBER_SHAPE => Ok(Box::new(WordPegEncoding::number_shape())),
+        ENCODING_NAME_NUMBER_RHYME => Ok(Box::new(WordPegEncoding::number_rhyme())),
+        _ => Ok(Box::new(TableScheme::from_name_or_path(name_or_path)?)),
+    }
+}
+
+/// Gives every word in the list its code from its spelling, replacing any codes it had, and adds
+/// the encoding's fixed words. This is the spelling counterpart of loading pronunciations into the
+/// list, for encodings without a phonetic scheme.
+pub fn encode_word_list(word_list: &mut WordList, encoding: &dyn Encoding) {
+    for word in word_list.words.values_mut() {
+        word.mnemonics.clear();
+        word.guessed = false;
+        if let Ok(code) = encoding.encode_word(&word.word, &[]) {
+            if !code.is_empty() {
+                word.mnemonics.insert(code);
+            }
+        }
+    }
+    // Rank them with the least common words so that a frequency list that has them decides.
+    let rank = word_list.words.len() + 1;
+    for (fixed_word, code) in encoding.fixed_words() {
+        word_list.words.entry(fixed_word.clone()).or_insert_with(|| Word {
+            word: fixed_word,
+            rank,
+            frequency: 0,
+            dispersion: 1.0,
+            part_of_speech: "n".to_string(),
+            mnemonics: Some(code).into_iter().collect(),
+            guessed: false,
+            concreteness: None,
+        });
+    }
+}
+
+/// Keeps the encoding's fixed words in the index whatever max_rank says, since encode_word_list()
+/// ranks them behind the whole list.
+pub fn add_to_index_options(encoding: &dyn Encoding, options: &mut IndexOptions) {
+    options.always_include.extend(encoding.fixed_words().into_iter().map(|(fixed_word, _)| fixed_word.to_lowercase()));
+}
+
+/// Turns a phrase back into its number with whichever encoding is in use. Phonetic encodings go
+/// through decode_phrase(). For the others each word's code comes from its spelling, so there are
+/// no unknown words, just words that stand for nothing. Words in the pronunciations, which for
+/// these encodings are only the user's, keep the code given there.
+pub fn decode_phrase(pronunciations: &PronunciationMap, encoding: &dyn Encoding, phrase: &str, guess_unknown: bool) -> Result<PhraseDecoding, DecodeError> {
+    if let Some(scheme) = encoding.phonetic_scheme() {
+        return words::decode_phrase(pronunciations, scheme, phrase, guess_unknown);
+    }
+    let mut number = String::new();
+    let mut decoded_words = vec![];
+    for token in words::tokenize_phrase(phrase) {
+        let mnemonic = match pronunciations.get(&token.to_lowercase()) {
+            Some(variants) => variants[0].mnemonic.clone(),
+            None => encoding.encode_word(&token, &[])
+                .map_err(|message| DecodeError::Phone { word: token.clone(), message })?,
+        };
+        number.push_str(&mnemonic);
+        decoded_words.push(DecodedWord {
+            word: token,
+            mnemonic,
+            phones: vec![],
+            alternate_mnemonics: vec![],
+            guessed: false,
+        });
+    }
+    Ok(PhraseDecoding {
+        number,
+        words: decoded_words,
+    })
+}
@@ -0,0 +1,98 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// A failure while reading one of the data files: the word list, the pronunciation dictionary or
+/// a list of numbers.
+#[derive(Debug)]
+pub struct LoadError {
+    /// The path or other name of the source being read.
+    pub file: String,
+    /// One-based line number, if the failure was tied to a particular line.
+    pub line: Option<usize>,
+    /// The name of the column or field that failed, if any.
+    pub field: Option<String>,
+    pub kind: LoadErrorKind,
+}
+
+#[derive(Debug)]
+pub enum LoadErrorKind {
+    Io(io::Error),
+    MissingField,
+    InvalidValue(String),
+}
+
+impl LoadError {
+    pub fn io(file: &str, error: io::Error) -> Self {
+        Self {
+            file: file.to_string(),
+            line: None,
+            field: None,
+            kind: LoadErrorKind::Io(error),
+        }
+    }
+
+    pub fn missing_field(file: &str, line: usize, field: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            line: Some(line),
+            field: Some(field.to_string()),
+            kind: LoadErrorKind::MissingField,
+        }
+    }
+
+    pub fn invalid_value(file: &str, line: usize, field: &str, value: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            line: Some(line),
+            field: Some(field.to_string()),
+            kind: LoadErrorKind::InvalidValue(value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ", line {}", line)?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, ", field \"{}\"", field)?;
+        }
+        match &self.kind {
+            LoadErrorKind::Io(error) => write!(f, ": {}", error),
+            LoadErrorKind::MissingField => write!(f, ": missing"),
+            LoadErrorKind::InvalidValue(value) => write!(f, ": invalid value \"{}\"", value),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            LoadErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// A failure while turning a phrase back into digits.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Words that aren't in the pronunciation dictionary, in the order they appeared.
+    UnknownWords(Vec<String>),
+    /// A word whose pronunciation has a phone the scheme doesn't know.
+    Phone { word: String, message: String },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownWords(words) => write!(f, "Unknown words: {}", words.join(", ")),
+            DecodeError::Phone { word, message } => write!(f, "{} in \"{}\"", message, word),
+        }
+    }
+}
+
+impl Error for DecodeError {}
@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use crate::error::LoadError;
+use crate::mnemonic::IndexOptions;
+
+pub const CONTENT_FILTER_FILE_NAME: &str = "Content Filter.txt";
+
+// These are kept short on purpose: they only need to cover words likely to turn up in a
+// frequency list or pronunciation dictionary. Teams can add their own with load_user_lists().
+const SLUR_WORDS: [&str; 17] = [
+    "chink", "coon", "dyke", "fag", "faggot", "gook", "kike", "nigga", "nigger", "paki", "raghead",
+    "retard", "retarded", "spastic", "spic", "tranny", "wetback",
+];
+
+const PROFANITY_WORDS: [&str; 31] = [
+    "arse", "arsehole", "ass", "asshole", "bastard", "bitch", "bollocks", "bullshit", "cock",
+    "crap", "cunt", "damn", "dick", "dickhead", "fuck", "fucker", "fucking", "goddamn", "hell",
+    "jackass", "motherfucker", "piss", "pissed", "prick", "pussy", "shit", "shitty", "slut",
+    "twat", "wanker", "whore",
+];
+
+// Words that are fine in general but not on a card someone else has to look at.
+const SENSITIVE_WORDS: [&str; 33] = [
+    "abortion", "abuse", "bomb", "cancer", "corpse", "dead", "death", "die", "drug", "drunk",
+    "genocide", "gun", "hitler", "holocaust", "kill", "killer", "murder", "naked", "nazi", "nude",
+    "porn", "rape", "sex", "sexual", "sexy", "slave", "slavery", "suicide", "terror", "terrorist",
+    "torture", "victim", "weapon",
+];
+
+// Harmless words that are crude when one follows the other.
+const PROFANITY_PAIRS: [(&str, &str); 10] = [
+    ("ball", "sack"), ("beat", "meat"), ("blow", "job"), ("booty", "call"), ("butt", "plug"),
+    ("camel", "toe"), ("golden", "shower"), ("hand", "job"), ("hard", "on"), ("jerk", "off"),
+];
+
+const SENSITIVE_PAIRS: [(&str, &str); 6] = [
+    ("dead", "baby"), ("drug", "dealer"), ("hang", "yourself"), ("kill", "yourself"),
+    ("shoot", "up"), ("white", "power"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FilterCategory {
+    Slurs,
+    Profanity,
+    Sensitive,
+}
+
+/// How much to filter, from nothing at all to everything in every category.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strictness {
+    Off,
+    /// Slurs only.
+    Low,
+    /// Slurs and profanity.
+    Medium,
+    /// Slurs, profanity and sensitive words.
+    High,
+}
+
+/// Keeps offensive words and word pairs out of generated phrases. Single words are left out of the
+/// index by add_to_index_options(), and pairs are checked as phrases are built since each word of
+/// the pair is fine by itself.
+#[derive(Clone, Debug)]
+pub struct ContentFilter {
+    pub strictness: Strictness,
+    // Lowercase.
+    words: BTreeMap<String, FilterCategory>,
+    pairs: BTreeMap<(String, String), FilterCategory>,
+    // Words and "first second" pairs to let through even though a list has them.
+    allowed: BTreeSet<String>,
+}
+
+impl FilterCategory {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "slurs" => Some(FilterCategory::Slurs),
+            "profanity" => Some(FilterCategory::Profanity),
+            "sensitive" => Some(FilterCategory::Sensitive),
+            _ => None,
+        }
+    }
+}
+
+impl Strictness {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "off" => Ok(Strictness::Off),
+            "low" => Ok(Strictness::Low),
+            "medium" => Ok(Strictness::Medium),
+            "high" => Ok(Strictness::High),
+            _ => Err(format!("Unknown filter strictness \"{}\". Expected off, low, medium or high.", name)),
+        }
+    }
+
+    pub fn blocks(&self, category: FilterCategory) -> bool {
+        match category {
+            FilterCategory::Slurs => *self >= Strictness::Low,
+            FilterCategory::Profanity => *self >= Strictness::Medium,
+            FilterCategory::Sensitive => *self >= Strictness::High,
+        }
+    }
+}
+
+impl ContentFilter {
+    /// A filter with the built-in lists.
+    pub fn new(strictness: Strictness) -> Self {
+        let mut filter = Self {
+            strictness,
+            words: BTreeMap::new(),
+            pairs: BTreeMap::new(),
+            allowed: BTreeSet::new(),
+        };
+        for (category, words) in [(FilterCategory::Slurs, &SLUR_WORDS[..]), (FilterCategory::Profanity, &PROFANITY_WORDS[..]), (FilterCategory::Sensitive, &SENSITIVE_WORDS[..])].iter() {
+            for word in words.iter() {
+                filter.add_word(word, *category);
+            }
+        }
+        for (category, pairs) in [(FilterCategory::Profanity, &PROFANITY_PAIRS[..]), (FilterCategory::Sensitive, &SENSITIVE_PAIRS[..])].iter() {
+            for (first, second) in pairs.iter() {
+                filter.add_pair(first, second, *category);
+            }
+        }
+        filter
+    }
+
+    pub fn add_word(&mut self, word: &str, category: FilterCategory) {
+        self.words.insert(word.to_lowercase(), category);
+    }
+
+    pub fn add_pair(&mut self, first: &str, second: &str, category: FilterCategory) {
+        self.pairs.insert((first.to_lowercase(), second.to_lowercase()), category);
+    }
+
+    /// Lets a word, or a pair written as "first second", through whatever the lists say.
+    pub fn allow(&mut self, word_or_pair: &str) {
+        self.allowed.insert(word_or_pair.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase());
+    }
+
+    pub fn load_user_lists<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        let file = File::open(path.as_ref()).map_err(|e| LoadError::io(&file_name, e))?;
+        self.user_lists_from_reader(BufReader::new(file), &file_name)
+    }
+
+    /// Reads extra words and pairs in sections named for the categories, plus an [allow] section
+    /// for entries to let through:
+    ///
+    /// ```text
+    /// [profanity]
+    /// frak
+    /// [sensitive]
+    /// tax audit
+    /// [allow]
+    /// hell
+    /// ```
+    ///
+    /// A line with two words is a pair. Anything after "#" is a comment.
+    pub fn user_lists_from_reader<R: BufRead>(&mut self, reader: R, source_name: &str) -> Result<(), LoadError> {
+        // None is the [allow] section.
+        let mut section = None;
+        let mut in_section = false;
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            let line = line.split('#').next().unwrap().trim().to_lowercase();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                let name = line.trim_start_matches('[').trim_end_matches(']');
+                section = match name {
+                    "allow" => None,
+                    _ => Some(FilterCategory::from_name(name).ok_or_else(|| LoadError::invalid_value(source_name, line_number, "section", &line))?),
+                };
+                in_section = true;
+                continue;
+            }
+            if !in_section {
+                return Err(LoadError::missing_field(source_name, line_number, "section"));
+            }
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match (section, words.as_slice()) {
+                (None, _) => self.allow(&line),
+                (Some(category), [word]) => self.add_word(word, category),
+                (Some(category), [first, second]) => self.add_pair(first, second, category),
+                (Some(_), _) => return Err(LoadError::invalid_value(source_name, line_number, "entry", &line)),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn blocks_word(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        !self.allowed.contains(&word)
+            && self.words.get(&word).is_some_and(|category| self.strictness.blocks(*category))
+    }
+
+    pub fn blocks_pair(&self, first: &str, second: &str) -> bool {
+        let key = (first.to_lowercase(), second.to_lowercase());
+        !self.allowed.contains(&format!("{} {}", key.0, key.1))
+            && self.pairs.get(&key).is_some_and(|category| self.strictness.blocks(*category))
+    }
+
+    /// True if none of the words or neighbouring pairs are blocked.
+    pub fn allows_phrase(&self, words: &[String]) -> bool {
+        !words.iter().any(|word| self.blocks_word(word))
+            && !words.windows(2).any(|pair| self.blocks_pair(&pair[0], &pair[1]))
+    }
+
+    /// Adds every blocked single word to the options for building the index.
+    pub fn add_to_index_options(&self, options: &mut IndexOptions) {
+        options.blocked.extend(self.words.keys().filter(|word| self.blocks_word(word)).cloned());
+    }
+}
+
+impl Default for ContentFilter {
+    fn default() -> Self {
+        Self::new(Strictness::Medium)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mnemonic::IndexOptions;
+    use super::{ContentFilter, Strictness};
+
+    const USER_LISTS: &str = "\
+# Extra entries.
+[Profanity]
+frak
+[sensitive]
+tax  Audit  # A pair.
+[allow]
+hell
+drug dealer
+";
+
+    fn phrase(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn filter(strictness: Strictness) -> ContentFilter {
+        let mut filter = ContentFilter::new(strictness);
+        filter.user_lists_from_reader(USER_LISTS.as_bytes(), "content filter").unwrap();
+        filter
+    }
+
+    #[test]
+    fn user_lists_add_words_pairs_and_allowed_entries() {
+        let filter = filter(Strictness::High);
+        assert!(filter.blocks_word("Frak"));
+        assert!(filter.blocks_pair("tax", "audit"));
+        assert!(!filter.blocks_pair("audit", "tax"));
+        assert!(!filter.blocks_word("hell"));
+        assert!(!filter.blocks_pair("drug", "dealer"));
+        assert!(filter.blocks_word("drug"));
+    }
+
+    #[test]
+    fn user_lists_report_the_line_and_field() {
+        let cases = [
+            ("frak\n", 1, "section"),
+            ("[profanity]\nfrak\n[crude]\n", 3, "section"),
+            ("[sensitive]\n\ntax audit time\n", 3, "entry"),
+        ];
+        for (text, line, field) in cases.iter() {
+            let error = ContentFilter::new(Strictness::High).user_lists_from_reader(text.as_bytes(), "content filter").unwrap_err();
+            assert_eq!((error.line, error.field.as_deref()), (Some(*line), Some(*field)), "{:?}", text);
+        }
+        // Anything goes in [allow], including three words.
+        assert!(ContentFilter::new(Strictness::High).user_lists_from_reader("[allow]\ntax audit time\n".as_bytes(), "content filter").is_ok());
+    }
+
+    #[test]
+    fn blocks_pair_follows_the_strictness() {
+        let cases = [
+            (Strictness::Off, false, false),
+            (Strictness::Low, false, false),
+            (Strictness::Medium, true, false),
+            (Strictness::High, true, true),
+        ];
+        for (strictness, profanity, sensitive) in cases.iter() {
+            let filter = filter(*strictness);
+            assert_eq!(filter.blocks_pair("Hand", "JOB"), *profanity, "{:?}", strictness);
+            assert_eq!(filter.blocks_pair("dead", "baby"), *sensitive, "{:?}", strictness);
+            assert_eq!(filter.blocks_pair("tax", "audit"), *sensitive, "{:?}", strictness);
+            assert!(!filter.blocks_pair("hand", "baby"));
+        }
+    }
+
+    #[test]
+    fn allows_phrase_checks_words_and_neighbouring_pairs() {
+        let filter = filter(Strictness::Medium);
+        assert!(filter.allows_phrase(&phrase("a dead baby hand")));
+        assert!(filter.allows_phrase(&phrase("hand the job")));
+        assert!(!filter.allows_phrase(&phrase("the hand job")));
+        assert!(!filter.allows_phrase(&phrase("what the frak")));
+        assert!(filter.allows_phrase(&phrase("what the hell")));
+        assert!(filter.allows_phrase(&[]));
+    }
+
+    #[test]
+    fn add_to_index_options_blocks_only_what_the_strictness_does() {
+        let mut options = IndexOptions::new();
+        filter(Strictness::Medium).add_to_index_options(&mut options);
+        assert!(options.blocked.contains("frak"));
+        assert!(!options.blocked.contains("hell"));
+        assert!(!options.blocked.contains("drug"));
+    }
+}
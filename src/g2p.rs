@@ -0,0 +1,132 @@
+// A rough rule-based letter-to-sound converter for words that aren't in the pronunciation
+// dictionary. It's only trying to get the consonants right since those are what carry the digits,
+// so the vowels are approximate and everything gets primary stress.
+
+// Rules are tried in order at each position and the first match wins, so longer spellings come
+// before shorter ones. A rule marked "start" only applies at the beginning of the word and one
+// marked "end" only at the end.
+const RULES: [(&str, Position, &str); 61] = [
+    ("tch", Position::Any, "CH"),
+    ("tion", Position::Any, "SH AH0 N"),
+    ("sion", Position::Any, "ZH AH0 N"),
+    ("ough", Position::Any, "AO1"),
+    ("igh", Position::Any, "AY1"),
+    ("augh", Position::Any, "AO1"),
+    ("eigh", Position::Any, "EY1"),
+    ("kn", Position::Start, "N"),
+    ("gn", Position::Start, "N"),
+    ("gn", Position::End, "N"),
+    ("wr", Position::Start, "R"),
+    ("wh", Position::Start, "W"),
+    ("ps", Position::Start, "S"),
+    ("mb", Position::End, "M"),
+    ("gh", Position::Start, "G"),
+    ("gh", Position::Any, ""),
+    ("ch", Position::Any, "CH"),
+    ("sh", Position::Any, "SH"),
+    ("th", Position::Any, "TH"),
+    ("ph", Position::Any, "F"),
+    ("nge", Position::Any, "N JH EH1"),
+    ("ngi", Position::Any, "N JH IH1"),
+    ("ng", Position::Any, "NG"),
+    ("ck", Position::Any, "K"),
+    ("qu", Position::Any, "K W"),
+    ("dg", Position::Any, "JH"),
+    ("ce", Position::Any, "S EH1"),
+    ("ci", Position::Any, "S IH1"),
+    ("cy", Position::Any, "S IY1"),
+    ("ge", Position::Any, "JH EH1"),
+    ("gi", Position::Any, "JH IH1"),
+    ("gy", Position::Any, "JH IY1"),
+    ("ee", Position::Any, "IY1"),
+    ("ea", Position::Any, "IY1"),
+    ("oo", Position::Any, "UW1"),
+    ("ou", Position::Any, "AW1"),
+    ("ow", Position::Any, "OW1"),
+    ("ai", Position::Any, "EY1"),
+    ("ay", Position::Any, "EY1"),
+    ("oa", Position::Any, "OW1"),
+    ("oi", Position::Any, "OY1"),
+    ("oy", Position::Any, "OY1"),
+    ("au", Position::Any, "AO1"),
+    ("aw", Position::Any, "AO1"),
+    ("ew", Position::Any, "UW1"),
+    ("ar", Position::Any, "AA1 R"),
+    ("or", Position::Any, "AO1 R"),
+    ("er", Position::Any, "ER0"),
+    ("ir", Position::Any, "ER0"),
+    ("ur", Position::Any, "ER0"),
+    ("x", Position::Start, "Z"),
+    ("x", Position::Any, "K S"),
+    ("y", Position::Start, "Y"),
+    ("y", Position::Any, "IY0"),
+    ("c", Position::Any, "K"),
+    ("j", Position::Any, "JH"),
+    ("a", Position::Any, "AE1"),
+    ("e", Position::Any, "EH1"),
+    ("i", Position::Any, "IH1"),
+    ("o", Position::Any, "AA1"),
+    ("u", Position::Any, "AH1"),
+];
+
+const SINGLE_CONSONANTS: [(char, &str); 17] = [
+    ('b', "B"), ('d', "D"), ('f', "F"), ('g', "G"), ('h', "HH"), ('k', "K"), ('l', "L"),
+    ('m', "M"), ('n', "N"), ('p', "P"), ('q', "K"), ('r', "R"), ('s', "S"), ('t', "T"),
+    ('v', "V"), ('w', "W"), ('z', "Z"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Position {
+    Any,
+    Start,
+    End,
+}
+
+/// Guesses ARPAbet phones for a word from its spelling. Returns an empty list if the word has no
+/// letters.
+pub fn guess_phones(word: &str) -> Vec<String> {
+    let letters = word
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .collect::<String>();
+    // A final "e" after a consonant is usually silent as in "cake" or "lime". After "c" or "g" it
+    // still softens the consonant as in "race" or "change", so it's left for the rules.
+    let letters = match letters.strip_suffix('e') {
+        Some(stem) if stem.len() > 1 && !stem.ends_with(|c| "aeiouycg".contains(c)) => stem.to_string(),
+        _ => letters,
+    };
+    let mut phones: Vec<String> = vec![];
+    let mut index = 0;
+    while index < letters.len() {
+        let rest = &letters[index..];
+        let rule = RULES.iter().find(|(spelling, position, _)| {
+            rest.starts_with(spelling) && match position {
+                Position::Any => true,
+                Position::Start => index == 0,
+                Position::End => rest.len() == spelling.len(),
+            }
+        });
+        let (length, sounds) = match rule {
+            Some((spelling, _, sounds)) => (spelling.len(), *sounds),
+            None => {
+                let c = rest.chars().next().unwrap();
+                let sound = SINGLE_CONSONANTS.iter().find(|(letter, _)| *letter == c).map_or("", |(_, sound)| *sound);
+                (1, sound)
+            },
+        };
+        for sound in sounds.split_whitespace() {
+            // Double letters like "ll" or "tt" are a single sound.
+            if phones.last().is_some_and(|last| last == sound) && !is_vowel(sound) {
+                continue;
+            }
+            phones.push(sound.to_string());
+        }
+        index += length;
+    }
+    phones
+}
+
+fn is_vowel(phone: &str) -> bool {
+    phone.ends_with(|c: char| c.is_ascii_digit())
+}
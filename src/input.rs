@@ -0,0 +1,264 @@
+use std::fmt;
+
+/// What a number is, which decides where its natural breaks are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputKind {
+    Plain,
+    Phone,
+    Date,
+    Time,
+    Card,
+    Pin,
+}
+
+/// A number split into the groups a person would read it in, like ["1969", "07", "20"] for a
+/// date. Mnemonics are easier to remember with one word per group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedNumber {
+    pub kind: InputKind,
+    pub groups: Vec<String>,
+}
+
+impl InputKind {
+    /// A kind by name, or None for "auto" to detect the kind from the input.
+    pub fn from_name(name: &str) -> Result<Option<Self>, String> {
+        match name.to_lowercase().as_str() {
+            "auto" => Ok(None),
+            "plain" => Ok(Some(InputKind::Plain)),
+            "phone" => Ok(Some(InputKind::Phone)),
+            "date" => Ok(Some(InputKind::Date)),
+            "time" => Ok(Some(InputKind::Time)),
+            "card" => Ok(Some(InputKind::Card)),
+            "pin" => Ok(Some(InputKind::Pin)),
+            _ => Err(format!("Unknown input kind \"{}\". Expected auto, plain, phone, date, time, card or pin.", name)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputKind::Plain => "plain",
+            InputKind::Phone => "phone",
+            InputKind::Date => "date",
+            InputKind::Time => "time",
+            InputKind::Card => "card",
+            InputKind::Pin => "pin",
+        }
+    }
+
+    // How to split a number of this kind that was written without separators, by group length.
+    fn default_layout(&self, digits: &str) -> Vec<usize> {
+        let length = digits.len();
+        match (self, length) {
+            (InputKind::Date, 8) => vec![4, 2, 2],
+            (InputKind::Date, 6) => vec![2, 2, 2],
+            (InputKind::Time, 3) => vec![1, 2],
+            (InputKind::Time, 4) => vec![2, 2],
+            (InputKind::Time, 6) => vec![2, 2, 2],
+            (InputKind::Phone, 7) => vec![3, 4],
+            (InputKind::Phone, 10) => vec![3, 3, 4],
+            (InputKind::Phone, 11) if digits.starts_with('1') => vec![1, 3, 3, 4],
+            (InputKind::Card, 15) => vec![4, 6, 5],
+            (InputKind::Card, 13..=19) => (0..length).step_by(4).map(|start| (length - start).min(4)).collect(),
+            _ => vec![length],
+        }
+    }
+}
+
+impl fmt::Display for InputKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl ParsedNumber {
+    /// A single group, for callers that don't care about structure.
+    pub fn plain(number: &str) -> Self {
+        Self {
+            kind: InputKind::Plain,
+            groups: vec![digit_runs(number).concat()],
+        }
+    }
+
+    pub fn digits(&self) -> String {
+        self.groups.concat()
+    }
+
+    /// The offsets into digits() where one group ends and the next starts.
+    pub fn breaks(&self) -> Vec<usize> {
+        let mut breaks = vec![];
+        let mut offset = 0;
+        for group in self.groups.iter().take(self.groups.len().saturating_sub(1)) {
+            offset += group.len();
+            breaks.push(offset);
+        }
+        breaks
+    }
+}
+
+/// Works out what kind of number the text is from its punctuation and group lengths, keeping the
+/// groups as written. Anything that doesn't look like something else is Plain, split wherever
+/// the text had separators.
+pub fn detect(text: &str) -> ParsedNumber {
+    let groups = digit_runs(text);
+    let lengths = groups.iter().map(String::len).collect::<Vec<_>>();
+    let digit_count = lengths.iter().sum::<usize>();
+    let kind = if text.contains(':') && is_valid_time(&groups) {
+        InputKind::Time
+    } else if groups.len() == 3 && text.contains(&['-', '/', '.'][..]) && is_valid_date(&groups) {
+        InputKind::Date
+    } else if text.trim_start().starts_with('+') || text.contains('(') {
+        InputKind::Phone
+    } else if (13..=19).contains(&digit_count) && groups.len() >= 3 && (lengths == [4, 6, 5] || lengths[..lengths.len() - 1].iter().all(|&length| length == 4)) {
+        InputKind::Card
+    } else if groups.len() >= 2 && (7..=15).contains(&digit_count) {
+        InputKind::Phone
+    } else if groups.len() == 1 && (4..=6).contains(&digit_count) {
+        InputKind::Pin
+    } else {
+        InputKind::Plain
+    };
+    ParsedNumber {
+        kind,
+        groups,
+    }
+}
+
+/// Reads the text as the given kind. Groups come from the separators in the text, or if there
+/// aren't any, from the usual layout for the kind, such as 3-3-4 for a ten-digit phone number or
+/// YYYYMMDD for an eight-digit date.
+pub fn parse_as(text: &str, kind: InputKind) -> Result<ParsedNumber, String> {
+    let mut groups = digit_runs(text);
+    if groups.is_empty() {
+        return Err(format!("\"{}\" has no digits.", text));
+    }
+    if groups.len() == 1 {
+        let digits = groups.remove(0);
+        let mut start = 0;
+        for length in kind.default_layout(&digits) {
+            groups.push(digits[start..start + length].to_string());
+            start += length;
+        }
+    }
+    let digit_count = groups.iter().map(String::len).sum::<usize>();
+    let valid = match kind {
+        InputKind::Date => is_valid_date(&groups),
+        InputKind::Time => is_valid_time(&groups),
+        InputKind::Phone => (7..=15).contains(&digit_count),
+        InputKind::Card => (13..=19).contains(&digit_count),
+        InputKind::Pin | InputKind::Plain => true,
+    };
+    if !valid {
+        return Err(format!("\"{}\" doesn't look like a {}.", text, kind));
+    }
+    Ok(ParsedNumber {
+        kind,
+        groups,
+    })
+}
+
+/// Detects the kind if kind is None, otherwise parses the text as that kind.
+pub fn parse(text: &str, kind: Option<InputKind>) -> Result<ParsedNumber, String> {
+    match kind {
+        Some(kind) => parse_as(text, kind),
+        None => Ok(detect(text)),
+    }
+}
+
+fn digit_runs(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .filter(|run| !run.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Year first as in 1969-07-20, or year last as in 07/20/1969 or 20.07.69 with either the month
+// or the day first.
+fn is_valid_date(groups: &[String]) -> bool {
+    let values = groups.iter().map(|group| group.parse::<u32>().unwrap_or(0)).collect::<Vec<_>>();
+    match groups.iter().map(String::len).collect::<Vec<_>>().as_slice() {
+        [4, 1..=2, 1..=2] => (1..=12).contains(&values[1]) && (1..=31).contains(&values[2]),
+        [1..=2, 1..=2, 2] | [1..=2, 1..=2, 4] => {
+            let (first, second) = (values[0], values[1]);
+            (1..=31).contains(&first) && (1..=31).contains(&second) && (first <= 12 || second <= 12)
+        },
+        _ => false,
+    }
+}
+
+fn is_valid_time(groups: &[String]) -> bool {
+    let values = groups.iter().map(|group| group.parse::<u32>().unwrap_or(99)).collect::<Vec<_>>();
+    (2..=3).contains(&groups.len())
+        && groups.iter().all(|group| group.len() <= 2)
+        && values[0] <= 23
+        && values[1..].iter().all(|&value| value <= 59)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, parse_as, InputKind};
+
+    fn detected(text: &str) -> (InputKind, Vec<String>) {
+        let number = detect(text);
+        (number.kind, number.groups)
+    }
+
+    fn groups(groups: &[&str]) -> Vec<String> {
+        groups.iter().map(|group| group.to_string()).collect()
+    }
+
+    #[test]
+    fn detect_dates() {
+        assert_eq!(detected("1969-07-20"), (InputKind::Date, groups(&["1969", "07", "20"])));
+        assert_eq!(detected("07/20/1969"), (InputKind::Date, groups(&["07", "20", "1969"])));
+        assert_eq!(detected("20.7.69"), (InputKind::Date, groups(&["20", "7", "69"])));
+        // Neither the month nor the day can be 13 or more at once.
+        assert_ne!(detect("13/20/1969").kind, InputKind::Date);
+        assert_ne!(detect("1969-13-20").kind, InputKind::Date);
+        // Three-digit years aren't dates.
+        assert_ne!(detect("07/20/969").kind, InputKind::Date);
+    }
+
+    #[test]
+    fn detect_times() {
+        assert_eq!(detected("9:41"), (InputKind::Time, groups(&["9", "41"])));
+        assert_eq!(detected("23:59:59"), (InputKind::Time, groups(&["23", "59", "59"])));
+        assert_eq!(detect("24:00").kind, InputKind::Plain);
+        assert_eq!(detect("12:60").kind, InputKind::Plain);
+    }
+
+    #[test]
+    fn detect_phones() {
+        assert_eq!(detected("+1 (555) 123-4567"), (InputKind::Phone, groups(&["1", "555", "123", "4567"])));
+        assert_eq!(detected("555-123-4567"), (InputKind::Phone, groups(&["555", "123", "4567"])));
+        assert_eq!(detect("867-5309").kind, InputKind::Phone);
+    }
+
+    #[test]
+    fn detect_cards() {
+        assert_eq!(detect("4111 1111 1111 1111").kind, InputKind::Card);
+        assert_eq!(detect("3782 822463 10005").kind, InputKind::Card);
+        assert_eq!(detect("6011 0009 9013 9424 123").kind, InputKind::Card);
+        // Too short for a card, so the separators make it a phone number.
+        assert_eq!(detect("4111 1111 1111").kind, InputKind::Phone);
+    }
+
+    #[test]
+    fn detect_pins_and_plain_numbers() {
+        assert_eq!(detected("1234"), (InputKind::Pin, groups(&["1234"])));
+        assert_eq!(detect("123456").kind, InputKind::Pin);
+        assert_eq!(detected("3141592653"), (InputKind::Plain, groups(&["3141592653"])));
+        assert_eq!(detected("42"), (InputKind::Plain, groups(&["42"])));
+        assert_eq!(detected(""), (InputKind::Plain, groups(&[])));
+    }
+
+    #[test]
+    fn parse_as_uses_the_usual_layout() {
+        assert_eq!(parse_as("19690720", InputKind::Date).unwrap().groups, groups(&["1969", "07", "20"]));
+        assert_eq!(parse_as("5551234567", InputKind::Phone).unwrap().groups, groups(&["555", "123", "4567"]));
+        assert_eq!(parse_as("15551234567", InputKind::Phone).unwrap().groups, groups(&["1", "555", "123", "4567"]));
+        assert_eq!(parse_as("4111111111111111", InputKind::Card).unwrap().breaks(), vec![4, 8, 12]);
+        assert!(parse_as("19691320", InputKind::Date).is_err());
+        assert!(parse_as("123", InputKind::Phone).is_err());
+        assert!(parse_as("none", InputKind::Plain).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+
+// ARPAbet phones and their usual IPA for General American. Vowels are listed without stress. AH
+// and ER have their own unstressed forms, handled in arpabet_to_ipa().
+const ARPABET_IPA_TABLE: [(&str, &str); 39] = [
+    ("AA", "ɑ"), ("AE", "æ"), ("AH", "ʌ"), ("AO", "ɔ"), ("AW", "aʊ"), ("AY", "aɪ"),
+    ("B", "b"), ("CH", "tʃ"), ("D", "d"), ("DH", "ð"), ("EH", "ɛ"), ("ER", "ɝ"),
+    ("EY", "eɪ"), ("F", "f"), ("G", "ɡ"), ("HH", "h"), ("IH", "ɪ"), ("IY", "i"),
+    ("JH", "dʒ"), ("K", "k"), ("L", "l"), ("M", "m"), ("N", "n"), ("NG", "ŋ"),
+    ("OW", "oʊ"), ("OY", "ɔɪ"), ("P", "p"), ("R", "ɹ"), ("S", "s"), ("SH", "ʃ"),
+    ("T", "t"), ("TH", "θ"), ("UH", "ʊ"), ("UW", "u"), ("V", "v"), ("W", "w"),
+    ("Y", "j"), ("Z", "z"), ("ZH", "ʒ"),
+];
+
+// IPA phones that aren't in the table above but have a close ARPAbet equivalent, as found in
+// British and broad transcriptions. Affricates English doesn't have are two phones.
+const IPA_ARPABET_ALIASES: [(&str, &str); 18] = [
+    ("ə", "AH"), ("ɚ", "ER"), ("ɜ", "ER"), ("g", "G"), ("r", "R"), ("ʁ", "R"), ("ɾ", "T"),
+    ("ɒ", "AA"), ("a", "AA"), ("e", "EH"), ("o", "OW"), ("ɫ", "L"), ("ɐ", "AH"), ("əʊ", "OW"),
+    ("ʔ", "T"), ("ts", "T S"), ("dz", "D Z"), ("pf", "P F"),
+];
+
+// Affricates and diphthongs written as two letters without a tie bar. Each is read as one phone.
+const TWO_LETTER_PHONES: [&str; 14] = [
+    "tʃ", "dʒ", "ts", "dz", "pf", "tɕ", "dʑ", "aɪ", "aʊ", "eɪ", "oʊ", "ɔɪ", "ɔʏ", "əʊ",
+];
+
+const VOWELS: &str = "aeiouyæɑɐɒɛɜɝɚəɘɪɨʉʊʌɔøœɤɯɵɶʏ";
+
+const PRIMARY_STRESS: char = 'ˈ';
+const SECONDARY_STRESS: char = 'ˌ';
+
+/// How strongly a vowel is stressed, numbered like ARPAbet's stress digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stress {
+    Unstressed,
+    Primary,
+    Secondary,
+}
+
+/// One phone of an IPA transcription. The symbol is the bare phone, like "aː" becomes "a" and
+/// "t͡ʃ" becomes "tʃ", so that phonetic tables only need one entry per sound.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IpaPhone {
+    pub symbol: String,
+    pub long: bool,
+    // Only vowels carry stress. The mark comes before the syllable in IPA but belongs to its
+    // vowel here, as in ARPAbet.
+    pub stress: Stress,
+}
+
+impl Stress {
+    pub fn digit(&self) -> char {
+        match self {
+            Stress::Unstressed => '0',
+            Stress::Primary => '1',
+            Stress::Secondary => '2',
+        }
+    }
+}
+
+impl IpaPhone {
+    pub fn is_vowel(&self) -> bool {
+        self.symbol.chars().next().is_some_and(|c| VOWELS.contains(c))
+    }
+}
+
+impl fmt::Display for IpaPhone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.stress {
+            Stress::Primary => write!(f, "{}", PRIMARY_STRESS)?,
+            Stress::Secondary => write!(f, "{}", SECONDARY_STRESS)?,
+            Stress::Unstressed => {},
+        }
+        write!(f, "{}{}", self.symbol, if self.long { "ː" } else { "" })
+    }
+}
+
+/// Splits an IPA transcription like "/ˈʃtʁaːsə/" or "[ˈt͡ʃɪp]" into phones. Slashes and brackets
+/// around it, syllable breaks and spaces are dropped. Affricates and diphthongs are one phone
+/// whether or not they're tied, length marks and stress marks are recorded on the phone, and other
+/// diacritics like aspiration or nasalization are dropped.
+pub fn parse(transcription: &str) -> Vec<IpaPhone> {
+    let chars = transcription.trim().trim_matches(|c| c == '/' || c == '[' || c == ']').chars().collect::<Vec<_>>();
+    let mut phones: Vec<IpaPhone> = vec![];
+    let mut stress = Stress::Unstressed;
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        index += 1;
+        match c {
+            PRIMARY_STRESS | '\'' => stress = Stress::Primary,
+            SECONDARY_STRESS => stress = Stress::Secondary,
+            'ː' | 'ˑ' | ':' => {
+                if let Some(phone) = phones.last_mut() {
+                    phone.long = true;
+                }
+            },
+            // Tie bars above and below join the phones on either side, like "t͡ʃ".
+            '\u{361}' | '\u{35c}' => {
+                if let (Some(phone), Some(next)) = (phones.last_mut(), chars.get(index)) {
+                    phone.symbol.push(*next);
+                    index += 1;
+                }
+            },
+            // A rhotic hook makes "ə˞" the same vowel as "ɚ".
+            '˞' => {
+                if let Some(phone) = phones.last_mut() {
+                    phone.symbol = phone.symbol.replace('ə', "ɚ").replace('ɜ', "ɝ");
+                }
+            },
+            '.' | '-' | '‿' | '|' => {},
+            c if c.is_whitespace() || is_diacritic(c) => {},
+            c => {
+                let mut symbol = c.to_string();
+                if let Some(&next) = chars.get(index) {
+                    let pair = format!("{}{}", c, next);
+                    if TWO_LETTER_PHONES.contains(&pair.as_str()) {
+                        symbol = pair;
+                        index += 1;
+                    }
+                }
+                let mut phone = IpaPhone {
+                    symbol,
+                    long: false,
+                    stress: Stress::Unstressed,
+                };
+                if phone.is_vowel() {
+                    phone.stress = stress;
+                    stress = Stress::Unstressed;
+                }
+                phones.push(phone);
+            },
+        }
+    }
+    phones
+}
+
+/// The bare phones of an IPA transcription, ready for a phonetic scheme's table.
+pub fn tokenize(transcription: &str) -> Vec<String> {
+    parse(transcription).into_iter().map(|phone| phone.symbol).collect()
+}
+
+/// ARPAbet phones like "K AE1 T" as IPA like "kˈæt". Stress marks go right before the vowel since
+/// ARPAbet doesn't mark syllable breaks.
+pub fn arpabet_to_ipa(phones: &[String]) -> Result<String, String> {
+    let mut ipa = String::new();
+    for phone in phones.iter() {
+        let stress = phone.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let base = phone.trim_end_matches(|c: char| c.is_ascii_digit()).to_uppercase();
+        let symbol = match (base.as_str(), stress) {
+            ("AH", "0") => "ə",
+            ("ER", "0") => "ɚ",
+            (base, _) => ARPABET_IPA_TABLE.iter()
+                .find(|(arpabet, _)| *arpabet == base)
+                .map(|(_, ipa)| *ipa)
+                .ok_or_else(|| format!("Unexpected phone = {}", phone))?,
+        };
+        match stress {
+            "1" => ipa.push(PRIMARY_STRESS),
+            "2" => ipa.push(SECONDARY_STRESS),
+            _ => {},
+        }
+        ipa.push_str(symbol);
+    }
+    Ok(ipa)
+}
+
+/// An IPA transcription as ARPAbet phones with stress digits on the vowels, for using an IPA
+/// lexicon with the ARPAbet schemes. Sounds English doesn't have are an error.
+pub fn ipa_to_arpabet(transcription: &str) -> Result<Vec<String>, String> {
+    let mut arpabet = vec![];
+    for phone in parse(transcription).iter() {
+        let phones = ARPABET_IPA_TABLE.iter().copied()
+            .chain(IPA_ARPABET_ALIASES.iter().map(|&(ipa, arpabet)| (arpabet, ipa)))
+            .find(|&(_, ipa)| ipa == phone.symbol)
+            .map(|(arpabet, _)| arpabet)
+            .ok_or_else(|| format!("No ARPAbet phone for {} in {}", phone.symbol, transcription))?;
+        for arpabet_phone in phones.split_whitespace() {
+            arpabet.push(if phone.is_vowel() {
+                format!("{}{}", arpabet_phone, phone.stress.digit())
+            } else {
+                arpabet_phone.to_string()
+            });
+        }
+    }
+    Ok(arpabet)
+}
+
+// Combining marks like the nasal tilde and modifier letters like aspiration.
+fn is_diacritic(c: char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c) || "ʰʲʷˠˤⁿˡʼ".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arpabet_to_ipa, ipa_to_arpabet, parse, tokenize, IpaPhone, Stress};
+
+    fn phone(symbol: &str, long: bool, stress: Stress) -> IpaPhone {
+        IpaPhone {
+            symbol: symbol.to_string(),
+            long,
+            stress,
+        }
+    }
+
+    #[test]
+    fn parse_marks_length_and_stress_on_the_vowel() {
+        assert_eq!(parse("/ˈʃtʁaːsə/"), vec![
+            phone("ʃ", false, Stress::Unstressed),
+            phone("t", false, Stress::Unstressed),
+            phone("ʁ", false, Stress::Unstressed),
+            phone("a", true, Stress::Primary),
+            phone("s", false, Stress::Unstressed),
+            phone("ə", false, Stress::Unstressed),
+        ]);
+        assert_eq!(parse("ˌɪn.fɚˈmeɪ.ʃən").iter().filter(|phone| phone.stress != Stress::Unstressed).cloned().collect::<Vec<_>>(), vec![
+            phone("ɪ", false, Stress::Secondary),
+            phone("eɪ", false, Stress::Primary),
+        ]);
+        assert_eq!(parse("'kat")[1], phone("a", false, Stress::Primary));
+    }
+
+    #[test]
+    fn parse_joins_affricates_and_diphthongs() {
+        assert_eq!(tokenize("[ˈt͡ʃɪp]"), vec!["tʃ", "ɪ", "p"]);
+        assert_eq!(tokenize("tʃɪp"), vec!["tʃ", "ɪ", "p"]);
+        assert_eq!(tokenize("d͜ʒ"), vec!["dʒ"]);
+        assert_eq!(tokenize("ˈkat͡sə"), vec!["k", "a", "ts", "ə"]);
+        assert_eq!(tokenize("haʊs"), vec!["h", "aʊ", "s"]);
+    }
+
+    #[test]
+    fn parse_drops_diacritics_and_separators() {
+        assert_eq!(tokenize("/ˈpʰɛ̃n.sl̩/"), vec!["p", "ɛ", "n", "s", "l"]);
+        assert_eq!(tokenize("a‿b | c-d"), vec!["a", "b", "c", "d"]);
+        assert_eq!(tokenize("ə˞"), vec!["ɚ"]);
+    }
+
+    #[test]
+    fn parse_handles_empty_and_mark_only_input() {
+        assert!(parse("").is_empty());
+        assert!(parse("/ /").is_empty());
+        assert!(parse("[ˈː.]").is_empty());
+        // A tie bar or length mark with nothing before it is dropped.
+        assert_eq!(tokenize("͡ʃa"), vec!["ʃ", "a"]);
+    }
+
+    #[test]
+    fn arpabet_round_trip() {
+        let cat = vec!["K".to_string(), "AE1".to_string(), "T".to_string()];
+        assert_eq!(arpabet_to_ipa(&cat), Ok("kˈæt".to_string()));
+        assert_eq!(ipa_to_arpabet("kˈæt"), Ok(cat));
+        assert_eq!(ipa_to_arpabet("/ˈkæts/"), Ok(vec!["K".to_string(), "AE1".to_string(), "T".to_string(), "S".to_string()]));
+        assert!(ipa_to_arpabet("/ç/").is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::fs;
+use std::path::Path;
+use crate::error::LoadError;
+use crate::scheme::{PhoneticScheme, TableScheme};
+use crate::words::{self, Pronunciation, Variants, WordList};
+
+pub const LANGUAGE_NAME_ENGLISH: &str = "en";
+pub const LANGUAGE_NAME_GERMAN_SAMPLE: &str = "de-sample";
+
+// A few dozen common German words, enough to try the German Major System without downloading a
+// frequency list and lexicon. Word, count and part of speech.
+const GERMAN_SAMPLE_WORDS: &str = "\
+Haus\t900\tn
+neu\t600\tj
+Buch\t520\tn
+Tisch\t500\tn
+Geld\t480\tn
+Hund\t450\tn
+rot\t420\tj
+Tee\t400\tn
+Katze\t380\tn
+Nase\t350\tn
+laufen\t340\tv
+Ei\t330\tn
+Zug\t330\tn
+Bau\t310\tn
+Maus\t300\tn
+Schuh\t280\tn
+Zahn\t270\tn
+Kuh\t260\tn
+Rose\t250\tn
+Dach\t240\tn
+Milch\t230\tn
+Wein\t210\tn
+Löwe\t200\tn
+Bein\t190\tn
+Ring\t180\tn
+Pferd\t170\tn
+malen\t150\tv
+Fee\t120\tn
+Lamm\t90\tn
+Jäger\t80\tn
+";
+
+// Their pronunciations in IPA with one phone per token, the way the German scheme expects.
+const GERMAN_SAMPLE_LEXICON: &str = "\
+Haus  h aʊ s
+neu  n ɔʏ
+Buch  b uː x
+Tisch  t ɪ ʃ
+Geld  g ɛ l t
+Hund  h ʊ n t
+rot  ʁ oː t
+Tee  t eː
+Katze  k a ts ə
+Nase  n aː z ə
+laufen  l aʊ f ə n
+Ei  aɪ
+Zug  ts uː k
+Bau  b aʊ
+Maus  m aʊ s
+Schuh  ʃ uː
+Zahn  ts aː n
+Kuh  k uː
+Rose  ʁ oː z ə
+Dach  d a x
+Milch  m ɪ l ç
+Wein  v aɪ n
+Löwe  l øː v ə
+Bein  b aɪ n
+Ring  ʁ ɪ ŋ
+Pferd  pf eː ɐ t
+malen  m aː l ə n
+Fee  f eː
+Lamm  l a m
+Jäger  j ɛː g ɐ
+";
+
+/// Where the words and their frequencies come from.
+#[derive(Clone, Debug)]
+pub enum WordSource {
+    /// A file in the "English Words Top 5000" layout, read by WordList::from_reader().
+    RankedList(String),
+    /// A file of word and count lines, read by WordList::from_counts_reader().
+    Counts(String),
+    /// Word and count lines built into the program.
+    BuiltIn(&'static str),
+}
+
+/// Where the pronunciations come from.
+#[derive(Clone, Debug)]
+pub enum PronunciationSource {
+    /// A word followed by its phones on each line, like the CMU dictionary or a lexicon with one
+    /// IPA phone per token, read by Pronunciation::from_reader().
+    Dictionary(String),
+    /// A word followed by its IPA transcription on each line, read by
+    /// Pronunciation::from_ipa_reader().
+    IpaLexicon(String),
+    BuiltIn(&'static str),
+}
+
+/// Everything that ties the search to one language: its words, their pronunciations and the
+/// table from its phones to digits. English is the frequency list and CMU dictionary the program
+/// has always used. Other languages are described in a small file:
+///
+/// ```text
+/// name = "Deutsch"
+/// words = "German Words.txt"
+/// word_format = "counts"
+/// pronunciations = "German Lexicon.txt"
+/// pronunciation_format = "ipa"
+/// scheme = "major-de"
+/// ```
+///
+/// The word format is "counts" for word and count lines or "ranked" for the English layout. The
+/// pronunciation format is "phones" for phones separated by spaces or "ipa" for transcriptions
+/// like "/ˈʃtʁaːsə/". The scheme is a built-in scheme name or a scheme file. File names are
+/// relative to the language file.
+#[derive(Clone, Debug)]
+pub struct Language {
+    pub name: String,
+    pub words: WordSource,
+    pub pronunciations: PronunciationSource,
+    pub scheme: TableScheme,
+    // Whether words missing from the pronunciations get phones guessed from their spelling. The
+    // rules in g2p are for English.
+    pub guess_pronunciations: bool,
+}
+
+impl Language {
+    pub fn english(words_file: &str, pronunciations_file: &str) -> Self {
+        Self {
+            name: LANGUAGE_NAME_ENGLISH.to_string(),
+            words: WordSource::RankedList(words_file.to_string()),
+            pronunciations: PronunciationSource::Dictionary(pronunciations_file.to_string()),
+            scheme: TableScheme::major(),
+            guess_pronunciations: true,
+        }
+    }
+
+    /// The built-in German sample with the German Major System.
+    pub fn german_sample() -> Self {
+        Self {
+            name: LANGUAGE_NAME_GERMAN_SAMPLE.to_string(),
+            words: WordSource::BuiltIn(GERMAN_SAMPLE_WORDS),
+            pronunciations: PronunciationSource::BuiltIn(GERMAN_SAMPLE_LEXICON),
+            scheme: TableScheme::major_german(),
+            guess_pronunciations: false,
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            LANGUAGE_NAME_ENGLISH => Some(Self::english(words::WORD_FILE_NAME, words::PRONUNCIATION_FILE_NAME)),
+            LANGUAGE_NAME_GERMAN_SAMPLE => Some(Self::german_sample()),
+            _ => None,
+        }
+    }
+
+    /// Either a built-in language by name or a language file.
+    pub fn from_name_or_path(name_or_path: &str) -> Result<Self, String> {
+        match Self::built_in(name_or_path) {
+            Some(language) => Ok(language),
+            None => Self::from_path(name_or_path),
+        }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read language file {}: {}", path.display(), e))?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::parse(&text, directory).map_err(|message| format!("{} in {}", message, path.display()))
+    }
+
+    /// Reads a language file's text, taking relative file names in it from directory.
+    pub fn parse(text: &str, directory: &Path) -> Result<Self, String> {
+        let file_name = |value: &str| directory.join(value).display().to_string();
+        let mut name = None;
+        let mut words_file = None;
+        let mut word_format = "counts".to_string();
+        let mut pronunciations_file = None;
+        let mut pronunciation_format = "phones".to_string();
+        let mut scheme = None;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("Expected key = value on line {}", index + 1))?;
+            let value = value.trim().trim_matches('"').to_string();
+            match key.trim() {
+                "name" => name = Some(value),
+                "words" => words_file = Some(file_name(&value)),
+                "word_format" => word_format = value,
+                "pronunciations" => pronunciations_file = Some(file_name(&value)),
+                "pronunciation_format" => pronunciation_format = value,
+                "scheme" => scheme = Some(match TableScheme::built_in(&value) {
+                    Some(scheme) => scheme,
+                    None => TableScheme::from_path(file_name(&value))?,
+                }),
+                key => return Err(format!("Unexpected key {} on line {}", key, index + 1)),
+            }
+        }
+        let words_file = words_file.ok_or("Missing words")?;
+        let words = match word_format.as_str() {
+            "counts" => WordSource::Counts(words_file),
+            "ranked" => WordSource::RankedList(words_file),
+            _ => return Err(format!("Unknown word format {}. Expected counts or ranked", word_format)),
+        };
+        let pronunciations_file = pronunciations_file.ok_or("Missing pronunciations")?;
+        let pronunciations = match pronunciation_format.as_str() {
+            "phones" => PronunciationSource::Dictionary(pronunciations_file),
+            "ipa" => PronunciationSource::IpaLexicon(pronunciations_file),
+            _ => return Err(format!("Unknown pronunciation format {}. Expected phones or ipa", pronunciation_format)),
+        };
+        Ok(Self {
+            name: name.ok_or("Missing name")?,
+            words,
+            pronunciations,
+            scheme: scheme.ok_or("Missing scheme")?,
+            guess_pronunciations: false,
+        })
+    }
+
+    pub fn load_word_list(&self) -> Result<WordList, LoadError> {
+        match &self.words {
+            WordSource::RankedList(file_name) => WordList::from_path(file_name),
+            WordSource::Counts(file_name) => WordList::from_counts_path(file_name),
+            WordSource::BuiltIn(text) => WordList::from_counts_reader(text.as_bytes(), &format!("{} words", self.name)),
+        }
+    }
+
+    /// Reads the pronunciations like Pronunciation::from_reader() or from_ipa_reader(), giving the
+    /// words in the list their codes with the scheme. This is usually the language's own scheme,
+    /// but a variant of it works too.
+    pub fn load_pronunciations(&self, mut words: Option<&mut WordList>, scheme: &dyn PhoneticScheme, variants: Variants) -> Result<Vec<Pronunciation>, LoadError> {
+        let mut pronunciations = match &self.pronunciations {
+            PronunciationSource::Dictionary(file_name) => Pronunciation::from_path(file_name, words.as_deref_mut(), scheme, variants)?,
+            PronunciationSource::IpaLexicon(file_name) => Pronunciation::from_ipa_path(file_name, words.as_deref_mut(), scheme, variants)?,
+            PronunciationSource::BuiltIn(text) => Pronunciation::from_reader(text.as_bytes(), &format!("{} pronunciations", self.name), words.as_deref_mut(), scheme, variants)?,
+        };
+        if !self.guess_pronunciations {
+            pronunciations.retain(|pronunciation| !pronunciation.guessed);
+            if let Some(words) = words {
+                for word in words.words.values_mut().filter(|word| word.guessed) {
+                    word.mnemonics.clear();
+                    word.guessed = false;
+                }
+            }
+        }
+        Ok(pronunciations)
+    }
+
+    /// The word list with codes from the language's scheme, and the pronunciations.
+    pub fn load(&self, variants: Variants) -> Result<(WordList, Vec<Pronunciation>), LoadError> {
+        let mut word_list = self.load_word_list()?;
+        let pronunciations = self.load_pronunciations(Some(&mut word_list), &self.scheme, variants)?;
+        Ok((word_list, pronunciations))
+    }
+
+    /// Every word with a pronunciation rather than just those in the word list, as made by
+    /// WordList::from_pronunciations(). The word list only supplies ranks, and is optional: if its
+    /// file doesn't exist, every word gets the default rank.
+    pub fn load_all_words(&self, scheme: &dyn PhoneticScheme, variants: Variants, default_rank: usize) -> Result<(WordList, Vec<Pronunciation>), LoadError> {
+        let pronunciations = self.load_pronunciations(None, scheme, variants)?;
+        let has_word_list = match &self.words {
+            WordSource::RankedList(file_name) | WordSource::Counts(file_name) => Path::new(file_name).exists(),
+            WordSource::BuiltIn(_) => true,
+        };
+        let frequencies = if has_word_list { Some(self.load_word_list()?) } else { None };
+        let word_list = WordList::from_pronunciations(&pronunciations, frequencies.as_ref(), default_rank);
+        Ok((word_list, pronunciations))
+    }
+}
@@ -1,11 +1,50 @@
-#![feature(str_split_once)]
 //#[macro_use]
 extern crate itertools;
 
-extern crate util_rust;
-pub use util_rust::*;
+pub mod util;
+pub use util::*;
+
+pub mod anki;
+
+pub mod batch;
+
+pub mod encoding;
+
+pub mod error;
+
+pub mod filter;
+
+pub mod g2p;
+
+pub mod input;
+
+pub mod ipa;
+
+pub mod language;
 
 pub mod mnemonic;
 
+pub mod output;
+
+pub mod pao;
+
+pub mod pegs;
+
+pub mod practice;
+
+pub mod report;
+
+pub mod scheme;
+
+pub mod score;
+
+pub mod search;
+
+pub mod segmentation;
+
+pub mod template;
+
+pub mod user_dict;
+
 pub mod words;
 
@@ -1,30 +1,675 @@
-// use mnembus_2000_rust::*;
-use mnembus_2000_rust::{mnemonic, words};
-use util_rust::log;
+use mnembus_2000_rust::{anki, encoding, ipa, mnemonic, output, pao, pegs, practice, words};
+use mnembus_2000_rust::output::OutputFormat;
+use mnembus_2000_rust::pao::{PaoRole, PaoTable};
+use mnembus_2000_rust::pegs::PegOptions;
+use mnembus_2000_rust::practice::{Direction, Grade, PracticeState};
+use mnembus_2000_rust::batch::{self, BatchOptions};
+use mnembus_2000_rust::filter::{self, ContentFilter, Strictness};
+use mnembus_2000_rust::encoding::Encoding;
+use mnembus_2000_rust::input::{self, InputKind};
+use mnembus_2000_rust::language::Language;
+use mnembus_2000_rust::report::MnemonicReport;
+use mnembus_2000_rust::scheme::TableScheme;
+use mnembus_2000_rust::mnemonic::{IndexOptions, MnemonicOptions};
+use mnembus_2000_rust::score::WeightedScorer;
+use mnembus_2000_rust::search::{SearchEngine, SearchOptions};
+use mnembus_2000_rust::segmentation::SegmentationPolicy;
+use mnembus_2000_rust::template::PosTemplate;
+use mnembus_2000_rust::user_dict::{self, UserDictionary};
+use mnembus_2000_rust::words::{Pronunciation, PronunciationMap, Variants, WordList};
+use mnembus_2000_rust::util::log;
+use std::env;
+use std::path::Path;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+const USAGE: &str = "\
+Mnembus 2000
+
+Usage: mnembus-2000-rust <command> [options]
+
+Commands:
+    encode <number>            Propose mnemonics for a number
+    search <number>            Find the best mnemonics for a number of any length
+    batch <file>               Find the best mnemonics for each \"label<tab>number\" line in a file
+    anki <file> <deck file>    Make Anki cards from a batch file. The deck file is a TSV import file,
+                               or a package if it ends in .apkg
+    practice <file>            Quiz yourself on the numbers in a batch file that are due for review
+    pao build                  Propose a person, action and object for each code from 00 to 99 and
+                               fill in the ones not yet chosen in the PAO table. Persons are
+                               words tagged np, so add names under [persons] in the user
+                               dictionary
+    pao <number>               Encode a number as Person-Action-Object sentences, six digits each
+    pegs                       List the best pegs for every 1-, 2- and 3-digit code, flagging codes
+                               with only rare words or none at all within --max-rank
+    pegs <file>                Write the best peg for each code as a user dictionary [pegs]
+                               section, or as a table for printing if the file ends in .tsv
+    decode <phrase>            Show the number encoded by a phrase
+    ipa <transcription>        Show the phones of an IPA transcription like /ˈʃtʁaːsə/, their
+                               ARPAbet equivalents and the number they encode with major-ipa or
+                               the --scheme given
+    lookup <word>              Show a word's rank, part of speech and codes
+    survey words               Summarize the word list given with --words
+    survey pronunciations      Summarize the pronunciation dictionary given with --pronunciations
+
+Options:
+    --max-rank <n>             Only use words up to this frequency rank (default 5000, or no limit
+                               with --all-words)
+    --all-words                Use every word in the pronunciation dictionary, not just those in
+                               the word list. The word list still ranks the words it has, if the
+                               file is there
+    --default-rank <n>         Rank for words with --all-words that aren't in the word list
+                               (default 50000)
+    --max-words <n>            Use at most this many words per mnemonic (default 5 for encode, no
+                               limit for search and batch)
+    --any-length               Let search and batch use more than the fewest possible words
+    --template <pattern>       Only find phrases in search and batch that fit a part-of-speech
+                               pattern like \"j n v n\", with one code per word: j adjective, n
+                               noun, v verb, r adverb and so on. Slots can allow several codes like
+                               \"j/n\" or any word with \"*\". Also takes the name of a built-in
+                               template: adjective-noun, noun-verb, adjective-noun-verb,
+                               noun-verb-noun, adjective-noun-verb-noun or
+                               adjective-noun-verb-adjective-noun
+    --min-digits <n>           Cover at least this many digits with each word in search and batch
+    --max-digits <n>           Cover at most this many digits with each word in search and batch
+    --chunk <n>                Cover exactly this many digits with each word in search and batch,
+                               except the last, like 2 or 3 for a steady rhythm or 6 for PAO
+    --breaks <positions>       Split the number at these digit positions in search and batch,
+                               written as offsets like \"4,6\" or group lengths like \"4-2-2\".
+                               With --input, search splits between the groups too
+    --uneven-penalty <x>       Prefer splits in search and batch where each word covers about the
+                               same number of digits, adding this much to the score per digit of
+                               difference
+    --weak-rank <n>            Call a peg weak if its words are all rarer than this (default 2000)
+    --threads <n>              Worker threads for batch (default one per CPU)
+    --top <n>                  Show this many of the best mnemonics (default 5)
+    --scheme <name or file>    How words stand for digits: major, major-th, major-de, major-es,
+                               major-fr, major-ipa or a phonetic scheme file, or dominic for the
+                               first letter of each word (A=1 B=2 C=3 D=4 E=5 S=6 G=7 H=8 N=9
+                               O=0), or number-shape or number-rhyme for one picture word per
+                               digit (default the language's scheme)
+    --language <name or file>  Words, pronunciations and scheme for another language, from a
+                               language file or the built-in de-sample German sample. Replaces
+                               --words and --pronunciations (default en)
+    --format <format>          Output format for encode, search and batch: text, json, jsonl, csv
+                               or markdown (default text)
+    --label <label>            Label for the encode and search commands
+    --input <kind>             What the number for encode is: phone, date, time, card, pin, plain
+                               or auto to tell from its punctuation (default auto). Encode tries
+                               to use one word per group, like the year, month and day of a date
+    --deck <name>              Deck name for the anki command (default Mnembus)
+    --state <file>             Review schedule for the practice command (default Practice.txt)
+    --pao <file>               PAO table for the pao command (default PAO Table.txt)
+    --words <file>             Word frequency list
+    --pronunciations <file>    Pronunciation dictionary
+    --concreteness <file>      Concreteness or imageability norms, to prefer words that are easy
+                               to picture
+    --concreteness-weight <x>  How much to prefer concrete words (default 3 with a norms file)
+    --min-concreteness <x>     Only use words rated at least this concrete, from 0 for the most
+                               abstract word in the norms file to 1 for the most concrete
+    --user-dict <file>         Your own words, pegs and blocked words (default User Dictionary.txt
+                               if it exists)
+    --filter <level>           Leave out offensive words and word pairs: off, low (slurs), medium
+                               (slurs and profanity) or high (also sensitive words) (default medium)
+    --filter-words <file>      Extra words and pairs to filter or allow (default Content Filter.txt
+                               if it exists)
+    --primary-only             Ignore alternate pronunciations
+    --guess                    Guess the pronunciation of words missing from the dictionary when
+                               decoding or checking practice answers
+    --verbose                  Print the log when done
+";
+
+// The most fewest-word paths to list for the search command. Long numbers can have thousands.
+const SEARCH_PATH_LIMIT: usize = 10;
+
+const DEFAULT_MAX_RANK: usize = 5_000;
+
+struct Options {
+    max_rank: Option<usize>,
+    default_rank: usize,
+    max_words: Option<usize>,
+    top_n: usize,
+    weak_rank: usize,
+    any_length: bool,
+    all_words: bool,
+    template: Option<PosTemplate>,
+    threads: usize,
+    scheme: Option<String>,
+    language: Option<String>,
+    format: String,
+    label: Option<String>,
+    input_kind: Option<InputKind>,
+    deck: String,
+    state_file: String,
+    pao_file: String,
+    words_file: String,
+    pronunciations_file: String,
+    concreteness_file: Option<String>,
+    user_dict_file: Option<String>,
+    content_filter: ContentFilter,
+    segmentation: SegmentationPolicy,
+    concreteness_weight: Option<f64>,
+    min_concreteness: Option<f64>,
+    variants: Variants,
+    guess: bool,
+    verbose: bool,
+}
 
 fn main() {
-    println!("Mnembus 2000 - Start");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if let Err(message) = run(&args) {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (options, positional) = parse_args(args)?;
+    let output_format = OutputFormat::from_name(&options.format)?;
     log::clear();
+    let positional = positional.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    match positional.as_slice() {
+        ["encode", number] => {
+            if options.template.is_some() || options.segmentation != SegmentationPolicy::new() {
+                return Err("--template, --min-digits, --max-digits, --chunk, --breaks and --uneven-penalty only work with search and batch.".to_string());
+            }
+            let (word_list, index_options) = load_words(&options)?;
+            let words = mnemonic::gen_btreemap_with_options(&word_list, &index_options);
+            let label = options.label.clone().unwrap_or_else(|| number.to_string());
+            let number = input::parse(number, options.input_kind)?;
+            let scorer = scorer(&options);
+            let mnemonic_options = MnemonicOptions {
+                max_words: options.max_words.unwrap_or(5),
+                top_n: options.top_n,
+                content_filter: Some(&options.content_filter),
+                ..MnemonicOptions::new(&scorer)
+            };
+            let report = mnemonic::propose_mnemonics_path_grouped(&word_list, &words, &label, &number, &mnemonic_options);
+            print_reports(&[report], output_format);
+        },
+        ["search", number] => {
+            let (word_list, index_options) = load_words(&options)?;
+            let words = mnemonic::gen_btreemap_with_options(&word_list, &index_options);
+            let engine = SearchEngine::new(&word_list, &words, &scorer(&options));
+            let label = options.label.clone().unwrap_or_else(|| number.to_string());
+            let mut search_options = search_options(&options);
+            if options.input_kind.is_some() {
+                search_options.segmentation.breaks.extend(input::parse(number, options.input_kind)?.breaks());
+            }
+            let report = engine.report(&label, number, &search_options, SEARCH_PATH_LIMIT);
+            print_reports(&[report], output_format);
+        },
+        ["batch", file_name] => {
+            let reports = run_batch(&options, file_name)?;
+            print_reports(&reports, output_format);
+        },
+        ["anki", file_name, deck_file_name] => {
+            let reports = run_batch(&options, file_name)?;
+            let notes = anki::notes_from_reports(&reports);
+            for report in reports.iter().filter(|report| report.best.is_empty()) {
+                eprintln!("No mnemonic for {} ({}), skipping.", report.label, report.number);
+            }
+            save_deck(&notes, &options.deck, deck_file_name)?;
+            eprintln!("Wrote {} notes to {}.", notes.len(), deck_file_name);
+        },
+        ["practice", file_name] => {
+            run_practice(&options, file_name)?;
+        },
+        ["decode", phrase @ ..] if !phrase.is_empty() => {
+            decode(&options, &phrase.join(" "))?;
+        },
+        ["ipa", transcription @ ..] if !transcription.is_empty() => {
+            show_ipa(&options, &transcription.join(" "))?;
+        },
+        ["pao", "build"] => {
+            build_pao_table(&options)?;
+        },
+        ["pao", number] => {
+            let table = PaoTable::load(&options.pao_file).map_err(|e| e.to_string())?;
+            for sentence in table.encode(number)? {
+                println!("{}\t{}", sentence.chunk, sentence);
+            }
+        },
+        ["pegs"] => {
+            let entries = peg_table(&options)?;
+            println!("{}", pegs::format_text(&entries));
+        },
+        ["pegs", file_name] => {
+            let entries = peg_table(&options)?;
+            pegs::save(&entries, file_name)?;
+            eprintln!("Wrote {} pegs to {}.", entries.iter().filter(|entry| entry.best().is_some()).count(), file_name);
+        },
+        ["lookup", word] => {
+            let (word_list, _) = load_words(&options)?;
+            lookup(&word_list, word)?;
+        },
+        ["survey", "words"] => words::survey_words(&options.words_file).map_err(|e| e.to_string())?,
+        ["survey", "pronunciations"] => words::survey_pronunciations(&options.pronunciations_file).map_err(|e| e.to_string())?,
+        [] => {
+            print!("{}", USAGE);
+        },
+        _ => return Err(format!("Unexpected arguments: {}\n\n{}", positional.join(" "), USAGE)),
+    }
+    if options.verbose {
+        for entry in log::get() {
+            eprintln!("{}", entry);
+        }
+    }
+    Ok(())
+}
 
-    // words::survey_words();
-    // let words = words::WordList::fill();
-    // words::survey_pronunciations();
-    //bg!(words::Pronunciation::fill(Some(words)).iter().take(20).collect::<Vec<_>>());
-    // try_read_pronunciations();
-    try_propose_mnemonics();
+fn parse_args(args: &[String]) -> Result<(Options, Vec<String>), String> {
+    let mut options = Options {
+        max_rank: None,
+        default_rank: words::DEFAULT_UNRANKED_RANK,
+        max_words: None,
+        top_n: 5,
+        weak_rank: PegOptions::new().weak_rank,
+        any_length: false,
+        all_words: false,
+        template: None,
+        threads: 0,
+        scheme: None,
+        language: None,
+        format: "text".to_string(),
+        label: None,
+        input_kind: None,
+        deck: anki::DEFAULT_DECK_NAME.to_string(),
+        state_file: practice::PRACTICE_FILE_NAME.to_string(),
+        pao_file: pao::PAO_FILE_NAME.to_string(),
+        words_file: words::WORD_FILE_NAME.to_string(),
+        pronunciations_file: words::PRONUNCIATION_FILE_NAME.to_string(),
+        concreteness_file: None,
+        user_dict_file: None,
+        content_filter: ContentFilter::default(),
+        segmentation: SegmentationPolicy::new(),
+        concreteness_weight: None,
+        min_concreteness: None,
+        variants: Variants::All,
+        guess: false,
+        verbose: false,
+    };
+    let mut filter_words_file = None;
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg.clone());
+            continue;
+        }
+        match arg.as_str() {
+            "--primary-only" => options.variants = Variants::PrimaryOnly,
+            "--guess" => options.guess = true,
+            "--any-length" => options.any_length = true,
+            "--all-words" => options.all_words = true,
+            "--verbose" => options.verbose = true,
+            "--max-rank" | "--default-rank" | "--max-words" | "--top" | "--weak-rank" | "--threads" | "--scheme" | "--language" | "--format" | "--template" | "--label" | "--input" | "--deck" | "--state" | "--pao" | "--words" | "--pronunciations" | "--concreteness" | "--user-dict" | "--filter" | "--filter-words" | "--concreteness-weight" | "--min-concreteness" | "--min-digits" | "--max-digits" | "--chunk" | "--breaks" | "--uneven-penalty" => {
+                let value = args.next().ok_or_else(|| format!("Missing value for {}.", arg))?.clone();
+                match arg.as_str() {
+                    "--max-rank" => options.max_rank = Some(parse_number(arg, &value)?),
+                    "--default-rank" => options.default_rank = parse_number(arg, &value)?,
+                    "--max-words" => options.max_words = Some(parse_number(arg, &value)?),
+                    "--top" => options.top_n = parse_number(arg, &value)?,
+                    "--weak-rank" => options.weak_rank = parse_number(arg, &value)?,
+                    "--threads" => options.threads = parse_number(arg, &value)?,
+                    "--scheme" => options.scheme = Some(value),
+                    "--language" => options.language = Some(value),
+                    "--format" => options.format = value,
+                    "--template" => options.template = Some(PosTemplate::from_name_or_pattern(&value)?),
+                    "--label" => options.label = Some(value),
+                    "--input" => options.input_kind = InputKind::from_name(&value)?,
+                    "--deck" => options.deck = value,
+                    "--state" => options.state_file = value,
+                    "--pao" => options.pao_file = value,
+                    "--words" => options.words_file = value,
+                    "--concreteness" => options.concreteness_file = Some(value),
+                    "--user-dict" => options.user_dict_file = Some(value),
+                    "--filter" => options.content_filter.strictness = Strictness::from_name(&value)?,
+                    "--filter-words" => filter_words_file = Some(value),
+                    "--concreteness-weight" => options.concreteness_weight = Some(parse_float(arg, &value)?),
+                    "--min-concreteness" => options.min_concreteness = Some(parse_float(arg, &value)?),
+                    "--min-digits" => options.segmentation.min_digits = parse_number(arg, &value)?,
+                    "--max-digits" => options.segmentation.max_digits = parse_number(arg, &value)?,
+                    "--chunk" => options.segmentation.chunk_width = SegmentationPolicy::fixed_width(parse_number(arg, &value)?)?.chunk_width,
+                    "--breaks" => options.segmentation.breaks = SegmentationPolicy::parse_breaks(&value)?,
+                    "--uneven-penalty" => options.segmentation.uneven_penalty = Some(parse_float(arg, &value)?)
+                        .filter(|&penalty| penalty >= 0.0)
+                        .ok_or_else(|| format!("Expected zero or more for {} but found \"{}\".", arg, value))?,
+                    _ => options.pronunciations_file = value,
+                }
+            },
+            _ => return Err(format!("Unknown option {}.\n\n{}", arg, USAGE)),
+        }
+    }
+    let filter_words_file = filter_words_file
+        .or_else(|| Some(filter::CONTENT_FILTER_FILE_NAME.to_string()).filter(|file_name| Path::new(file_name).exists()));
+    if let Some(filter_words_file) = filter_words_file {
+        options.content_filter.load_user_lists(&filter_words_file).map_err(|e| e.to_string())?;
+    }
+    Ok((options, positional))
+}
 
-    dbg!(log::get());
-    println!("Mnembus 2000 - Done");
+fn parse_number(arg: &str, value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("Expected a number for {} but found \"{}\".", arg, value))
 }
 
-fn try_propose_mnemonics() {
-    let mut words = words::WordList::fill();
-    words::Pronunciation::fill(Some(&mut words));
-    //mnemonic::propose_mnemonics(&words, "Test", "70718", 3, 1_000);
-    //mnemonic::propose_mnemonics(&words, "Brian", "206-890-9233");
-    //mnemonic::MnemonicRun::new(&words, "Test", "70718", 3, 2_000); // One word: executive
-    //nemonic::MnemonicRun::new(&words, "Test", "890-9233", 4, 5_000);
-    //mnemonic::MnemonicRun::new(&words, "Brian", "206-890-9233", 5, 5_000);
-    mnemonic::main();
+// Scores are compared as NotNan, so NaN and infinity are refused here rather than panicking later.
+fn parse_float(arg: &str, value: &str) -> Result<f64, String> {
+    value.parse::<f64>().ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("Expected a number for {} but found \"{}\".", arg, value))
 }
 
+fn print_reports(reports: &[MnemonicReport], output_format: OutputFormat) {
+    let text = output::render(reports, output_format);
+    if text.ends_with('\n') {
+        print!("{}", text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+fn run_batch(options: &Options, file_name: &str) -> Result<Vec<MnemonicReport>, String> {
+    let (word_list, index_options) = load_words(options)?;
+    let batch_options = BatchOptions {
+        threads: options.threads,
+        search: search_options(options),
+        ..BatchOptions::new()
+    };
+    let progress = |done: usize, total: usize| eprint!("\r{} / {}", done, total);
+    let reports = batch::run_batch_from_file(&word_list, file_name, &index_options, &scorer(options), &batch_options, &progress)
+        .map_err(|e| e.to_string())?;
+    eprintln!();
+    Ok(reports)
+}
+
+#[cfg(feature = "apkg")]
+fn save_deck(notes: &[anki::AnkiNote], deck_name: &str, file_name: &str) -> Result<(), String> {
+    if file_name.ends_with(".apkg") {
+        anki::save_apkg(notes, deck_name, file_name)
+    } else {
+        anki::save_tsv(notes, deck_name, file_name)
+    }
+}
+
+#[cfg(not(feature = "apkg"))]
+fn save_deck(notes: &[anki::AnkiNote], deck_name: &str, file_name: &str) -> Result<(), String> {
+    if file_name.ends_with(".apkg") {
+        Err("Writing .apkg packages needs a build with the apkg feature. Use a .txt or .tsv file to write an import file instead.".to_string())
+    } else {
+        anki::save_tsv(notes, deck_name, file_name)
+    }
+}
+
+fn search_options(options: &Options) -> SearchOptions {
+    SearchOptions {
+        max_words: options.max_words.unwrap_or(usize::MAX),
+        top_n: options.top_n,
+        fewest_words: !options.any_length,
+        template: options.template.clone(),
+        content_filter: Some(options.content_filter.clone()),
+        segmentation: options.segmentation.clone(),
+    }
+}
+
+// The word list with its codes, concreteness ratings and the user dictionary applied, and the
+// options for building an index from it.
+fn load_words(options: &Options) -> Result<(WordList, IndexOptions), String> {
+    let language = load_language(options)?;
+    let encoding = load_encoding(options, &language)?;
+    let mut word_list = match encoding.phonetic_scheme() {
+        Some(scheme) if options.all_words => language.load_all_words(scheme, options.variants, options.default_rank)
+            .map_err(|e| e.to_string())?.0,
+        Some(scheme) => {
+            let mut word_list = language.load_word_list().map_err(|e| e.to_string())?;
+            language.load_pronunciations(Some(&mut word_list), scheme, options.variants)
+                .map_err(|e| e.to_string())?;
+            word_list
+        },
+        None => {
+            // The pronunciations only decide which words there are, since the codes come from
+            // spelling.
+            let mut word_list = if options.all_words {
+                language.load_all_words(&language.scheme, options.variants, options.default_rank).map_err(|e| e.to_string())?.0
+            } else {
+                language.load_word_list().map_err(|e| e.to_string())?
+            };
+            encoding::encode_word_list(&mut word_list, encoding.as_ref());
+            word_list
+        },
+    };
+    load_concreteness(options, &mut word_list)?;
+    let mut index_options = IndexOptions {
+        max_rank: options.max_rank.unwrap_or(if options.all_words { usize::MAX } else { DEFAULT_MAX_RANK }),
+        min_concreteness: options.min_concreteness,
+        ..IndexOptions::new()
+    };
+    options.content_filter.add_to_index_options(&mut index_options);
+    encoding::add_to_index_options(encoding.as_ref(), &mut index_options);
+    if let Some(user_dictionary) = load_user_dictionary(options)? {
+        user_dictionary.apply(&mut word_list, encoding.as_ref()).map_err(|e| e.to_string())?;
+        user_dictionary.add_to_index_options(&mut index_options);
+    }
+    Ok((word_list, index_options))
+}
+
+// Every pronunciation in the dictionary, not just those of the words in the word list, and the
+// user dictionary's words and pegs, for turning phrases back into numbers. Encodings that work
+// from spelling only need the user's.
+fn load_decode_pronunciations(options: &Options, language: &Language, encoding: &dyn Encoding) -> Result<PronunciationMap, String> {
+    let pronunciations = match encoding.phonetic_scheme() {
+        Some(scheme) => language.load_pronunciations(None, scheme, options.variants)
+            .map_err(|e| e.to_string())?,
+        None => vec![],
+    };
+    let mut pronunciations = Pronunciation::index(pronunciations);
+    if let Some(user_dictionary) = load_user_dictionary(options)? {
+        user_dictionary.add_to_pronunciations(&mut pronunciations, encoding).map_err(|e| e.to_string())?;
+    }
+    Ok(pronunciations)
+}
+
+// The language given with --language, or else English from --words and --pronunciations.
+fn load_language(options: &Options) -> Result<Language, String> {
+    match &options.language {
+        Some(name_or_path) => Language::from_name_or_path(name_or_path),
+        None => Ok(Language::english(&options.words_file, &options.pronunciations_file)),
+    }
+}
+
+// The encoding given with --scheme, or else the language's own scheme.
+fn load_encoding(options: &Options, language: &Language) -> Result<Box<dyn Encoding>, String> {
+    match &options.scheme {
+        Some(name_or_path) => encoding::from_name_or_path(name_or_path),
+        None => Ok(Box::new(language.scheme.clone())),
+    }
+}
+
+// The file given with --user-dict, or else the default one if it exists.
+fn load_user_dictionary(options: &Options) -> Result<Option<UserDictionary>, String> {
+    let file_name = match &options.user_dict_file {
+        Some(file_name) => file_name.clone(),
+        None if Path::new(user_dict::USER_DICTIONARY_FILE_NAME).exists() => user_dict::USER_DICTIONARY_FILE_NAME.to_string(),
+        None => return Ok(None),
+    };
+    UserDictionary::from_path(&file_name).map(Some).map_err(|e| e.to_string())
+}
+
+fn load_concreteness(options: &Options, word_list: &mut WordList) -> Result<(), String> {
+    if let Some(concreteness_file) = &options.concreteness_file {
+        let rated_count = word_list.load_concreteness(concreteness_file).map_err(|e| e.to_string())?;
+        log::log(&format!("{} words rated for concreteness.", rated_count));
+    }
+    Ok(())
+}
+
+fn scorer(options: &Options) -> WeightedScorer {
+    let default_weight = if options.concreteness_file.is_some() { 3.0 } else { 0.0 };
+    WeightedScorer {
+        concreteness_weight: options.concreteness_weight.unwrap_or(default_weight),
+        ..WeightedScorer::new()
+    }
+}
+
+fn decode(options: &Options, phrase: &str) -> Result<(), String> {
+    let language = load_language(options)?;
+    let encoding = load_encoding(options, &language)?;
+    let pronunciations = load_decode_pronunciations(options, &language, encoding.as_ref())?;
+    let decoding = encoding::decode_phrase(&pronunciations, encoding.as_ref(), phrase, options.guess).map_err(|e| e.to_string())?;
+    for word in decoding.words.iter() {
+        let phones = word.phones.iter()
+            .map(|(phone, digits)| if digits.is_empty() { phone.clone() } else { format!("{}={}", phone, digits) })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let alternates = if word.alternate_mnemonics.is_empty() {
+            "".to_string()
+        } else {
+            format!("\t(also {})", word.alternate_mnemonics.join(", "))
+        };
+        let guessed = if word.guessed { "\t(guessed)" } else { "" };
+        println!("{}\t{}\t{}{}{}", word.word, word.mnemonic, phones, alternates, guessed);
+    }
+    println!("{}", decoding.number);
+    Ok(())
+}
+
+fn show_ipa(options: &Options, transcription: &str) -> Result<(), String> {
+    let scheme = match &options.scheme {
+        Some(name_or_path) => TableScheme::from_name_or_path(name_or_path)?,
+        None => TableScheme::major_ipa(),
+    };
+    let phones = ipa::parse(transcription);
+    if phones.is_empty() {
+        return Err(format!("\"{}\" has no IPA phones.", transcription));
+    }
+    println!("Phones: {}", phones.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "));
+    match ipa::ipa_to_arpabet(transcription) {
+        Ok(arpabet) => println!("ARPAbet: {}", arpabet.join(" ")),
+        Err(message) => println!("ARPAbet: {}", message),
+    }
+    let code = Pronunciation::phones_to_mnemonic(&scheme, &ipa::tokenize(transcription))?;
+    println!("{}", code);
+    Ok(())
+}
+
+fn run_practice(options: &Options, file_name: &str) -> Result<(), String> {
+    let numbers = mnemonic::load_numbers(file_name).map_err(|e| e.to_string())?;
+    let today = practice::today();
+    let mut state = PracticeState::load(&options.state_file).map_err(|e| e.to_string())?;
+    let indexes = state.sync(&numbers, today);
+    let due_items = state.due_items(&indexes, today);
+    if due_items.is_empty() {
+        match indexes.iter().map(|&index| state.items[index].due).min() {
+            Some(next_due) => println!("Nothing is due. The next review is in {} days.", next_due - today),
+            None => println!("There are no numbers to practice."),
+        }
+        return Ok(());
+    }
+
+    let language = load_language(options)?;
+    let encoding = load_encoding(options, &language)?;
+    let pronunciations = load_decode_pronunciations(options, &language, encoding.as_ref())?;
+    let (word_list, index_options) = load_words(options)?;
+    let engine = SearchEngine::new(&word_list, &mnemonic::gen_btreemap_with_options(&word_list, &index_options), &scorer(options));
+    let hint_options = SearchOptions { top_n: 1, ..search_options(options) };
+
+    println!("{} due. Answer with digits or a phrase, or q to stop.", due_items.len());
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut prompt = |text: &str| -> Option<String> {
+        print!("{} ", text);
+        io::stdout().flush().ok()?;
+        lines.next()?.ok().map(|line| line.trim().to_string())
+    };
+    let (mut reviewed, mut lapsed) = (0, 0);
+    for index in due_items {
+        let item = &state.items[index];
+        let direction = item.direction();
+        let question = match direction {
+            Direction::LabelToNumber => format!("{}?", item.label),
+            Direction::NumberToPhrase => format!("{} ({}) as a phrase?", item.number, item.label),
+        };
+        let answer = match prompt(&question) {
+            Some(answer) if answer != "q" => answer,
+            _ => break,
+        };
+        let check = practice::check_answer(&pronunciations, encoding.as_ref(), item, direction, &answer, options.guess);
+        let grade = if check.correct {
+            match prompt("Right. [h]ard, [g]ood or [e]asy? (g)").as_deref() {
+                Some("h") => Grade::Hard,
+                Some("e") => Grade::Easy,
+                _ => Grade::Good,
+            }
+        } else {
+            if let Some(message) = check.message {
+                println!("{}", message);
+            } else if let Some(digits) = check.digits {
+                println!("That's {}.", digits);
+            }
+            let hint = engine.search(&item.number, &hint_options).into_iter().next()
+                .map_or("".to_string(), |mnemonic| format!(" Try \"{}\".", mnemonic.phrase));
+            println!("The number is {}.{}", item.number, hint);
+            lapsed += 1;
+            Grade::Wrong
+        };
+        state.items[index].review(grade, today);
+        state.save(&options.state_file)?;
+        reviewed += 1;
+    }
+    println!("Reviewed {}, missed {}. {} still due.", reviewed, lapsed, state.due_items(&indexes, today).len());
+    Ok(())
+}
+
+fn peg_table(options: &Options) -> Result<Vec<pegs::PegEntry>, String> {
+    let (word_list, index_options) = load_words(options)?;
+    let words = mnemonic::gen_btreemap_with_options(&word_list, &index_options);
+    let peg_options = PegOptions {
+        top_n: options.top_n,
+        weak_rank: options.weak_rank,
+        ..PegOptions::new()
+    };
+    Ok(pegs::peg_table(&word_list, &words, &scorer(options), &peg_options))
+}
+
+fn build_pao_table(options: &Options) -> Result<(), String> {
+    let (word_list, index_options) = load_words(options)?;
+    let words = mnemonic::gen_btreemap_with_options(&word_list, &index_options);
+    let candidates = pao::propose_candidates(&word_list, &words, &scorer(options), options.top_n);
+    for code_candidates in candidates.iter() {
+        let roles = PaoRole::ALL.iter()
+            .map(|role| format!("{}: {}", role, code_candidates.for_role(*role).iter().map(|choice| choice.word.as_str()).collect::<Vec<_>>().join(", ")))
+            .collect::<Vec<_>>();
+        println!("{}\t{}", code_candidates.code, roles.join("\t"));
+    }
+    let mut table = PaoTable::load(&options.pao_file).map_err(|e| e.to_string())?;
+    table.fill(&candidates);
+    table.save(&options.pao_file)?;
+    let incomplete_codes = table.incomplete_codes();
+    eprintln!("Wrote {}.", options.pao_file);
+    // The built-in word list is all lowercase without proper noun tags, so persons only ever come
+    // from the user.
+    if candidates.iter().all(|code_candidates| code_candidates.persons.is_empty()) && table.entries.values().any(|entry| entry.person.is_none()) {
+        eprintln!("No word in the word list can be a person. Persons are words tagged np, so add names under [persons] in the user dictionary like \"Nemo = 23\" and run pao build again, or type them into the person column of {}.", options.pao_file);
+    }
+    if !incomplete_codes.is_empty() {
+        eprintln!("{} codes still need a person, action or object: {}", incomplete_codes.len(), incomplete_codes.join(" "));
+    }
+    Ok(())
+}
+
+fn lookup(word_list: &WordList, word: &str) -> Result<(), String> {
+    let word = word_list.words.get(&word.to_lowercase())
+        .ok_or_else(|| format!("\"{}\" is not in the word list.", word))?;
+    println!("{}", word.word);
+    println!("rank\t{}", word.rank);
+    println!("part of speech\t{}", word.part_of_speech);
+    println!("frequency\t{}", word.frequency);
+    println!("codes\t{}", word.mnemonics.iter().cloned().collect::<Vec<_>>().join(" "));
+    if let Some(concreteness) = word.concreteness {
+        println!("concreteness\t{:.2}", concreteness);
+    }
+    if word.guessed {
+        println!("(pronunciation guessed from the spelling)");
+    }
+    Ok(())
+}
@@ -1,27 +1,81 @@
 use ordered_float::NotNan;
-use std::time::Instant;
-use std::collections::BTreeMap;
-use crate::words::WordList;
+use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound::{Excluded, Unbounded};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use crate::error::LoadError;
+use crate::filter::ContentFilter;
+use crate::input::{self, ParsedNumber};
+use crate::report::{MnemonicReport, PathCandidate, WordChoice};
+use crate::score::{ScoreBreakdown, Scorer};
+use crate::words::{Word, WordList};
 use crate::itertools::Itertools;
-use util_rust::{format, parse};
+use crate::util::parse;
 
-const FILE_NAME_NUMBERS: &str = "Numbers.txt";
+pub type WordsBTreeMap = BTreeMap<String, Vec<(String, usize)>>;
 
-type WordsBTreeMap = BTreeMap<String, Vec<(String, usize)>>;
-
-pub fn main() {
-    let words = WordList::fill_with_pronunciation();
-    //bg!(gen_paths("123456",4));
-    // propose_mnemonics_path(&words, "Executive", "70718", 5_000);
-    // propose_mnemonics_path(&words, "Executive Plus", "3707184", 5_000);
-    propose_mnemonics_path_from_file(&words, FILE_NAME_NUMBERS, 5_000);
+/// Which words go into the index built by gen_btreemap_with_options().
+#[derive(Clone, Debug)]
+pub struct IndexOptions {
+    pub max_rank: usize,
+    // If set, only words with a concreteness of at least this much. Words that weren't rated are
+    // left out too.
+    pub min_concreteness: Option<f64>,
+    // Lowercase words to keep whatever max_rank and min_concreteness say, like the user's own.
+    pub always_include: BTreeSet<String>,
+    // Lowercase words never to use. This beats everything else, including pegs.
+    pub blocked: BTreeSet<String>,
+    // For each code, the only lowercase words to use for it.
+    pub pegs: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Clone, Debug)]
-struct Mnemonic {
-    phrase: String,
-    word_count: usize,
-    score: NotNan<f64>,
+pub struct Mnemonic {
+    pub phrase: String,
+    pub words: Vec<String>,
+    // The code for each word, which together make up the segmentation of the number.
+    pub codes: Vec<String>,
+    pub word_count: usize,
+    // Lower is better. This is the total of the breakdown.
+    pub score: NotNan<f64>,
+    pub breakdown: ScoreBreakdown,
+}
+
+impl Mnemonic {
+    pub(crate) fn new() -> Self {
+        Self {
+            phrase: "".to_string(),
+            words: vec![],
+            codes: vec![],
+            word_count: 0,
+            score: NotNan::new(0.0).unwrap(),
+            breakdown: ScoreBreakdown::default(),
+        }
+    }
+
+    pub(crate) fn with_word(&self, word: &str, code: &str, breakdown: &ScoreBreakdown) -> Self {
+        let mut new_mnemonic = self.clone();
+        new_mnemonic.phrase = format!("{} {}", self.phrase, word).trim().to_string();
+        new_mnemonic.words.push(word.to_string());
+        new_mnemonic.codes.push(code.to_string());
+        new_mnemonic.word_count += 1;
+        new_mnemonic.breakdown.add(breakdown);
+        new_mnemonic.score = NotNan::new(new_mnemonic.breakdown.total()).unwrap();
+        new_mnemonic
+    }
+}
+
+/// What propose_mnemonics_path() and MnemonicRun look for, like SearchOptions for the search
+/// engine.
+#[derive(Clone)]
+pub struct MnemonicOptions<'a> {
+    pub scorer: &'a dyn Scorer,
+    pub max_words: usize,
+    pub top_n: usize,
+    // If set, phrases with a blocked pair of neighbouring words are skipped.
+    pub content_filter: Option<&'a ContentFilter>,
 }
 
 #[allow(dead_code)]
@@ -30,66 +84,98 @@ pub struct MnemonicRun {
     number: String,
     max_words: usize,
     max_rank: usize,
+    top_n: usize,
     // words: BTreeMap<String, Vec<(String, usize)>>,
     // start_time: Instant,
     // end_time: Option<Instant>,
     mnemonics: Vec<Mnemonic>,
+    elapsed_build_btree: Duration,
+    elapsed_propose: Duration,
+}
+
+impl<'a> MnemonicOptions<'a> {
+    pub fn new(scorer: &'a dyn Scorer) -> Self {
+        Self {
+            scorer,
+            max_words: 5,
+            top_n: 5,
+            content_filter: None,
+        }
+    }
 }
 
 impl MnemonicRun {
-    pub fn new(word_list: &WordList, label: &str, number: &str, max_words: usize, max_rank: usize) -> Self {
-        let start_time_overall = Instant::now();
+    pub fn new(word_list: &WordList, label: &str, number: &str, max_rank: usize, options: &MnemonicOptions) -> Self {
         let number = parse::digits_only(number);
         // One entry per mnemonic with multiple words possible per entry.
         let start_time_build_btree = Instant::now();
-        let mut words = BTreeMap::new();
-        for word in word_list.words.values().filter(|word| word.mnemonic.as_ref().is_some() && word.rank <= max_rank) {
-            let entry = words.entry(word.mnemonic.as_ref().unwrap().clone()).or_insert(vec![]);
-            entry.push((word.word.clone(), word.rank));
+        let mut index_options = IndexOptions { max_rank, ..IndexOptions::new() };
+        if let Some(content_filter) = options.content_filter {
+            content_filter.add_to_index_options(&mut index_options);
         }
+        let words = gen_btreemap_with_options(word_list, &index_options);
         let elapsed_build_btree = Instant::now() - start_time_build_btree;
         let mut run = Self {
             label: label.to_string(),
             number: number.to_string(),
-            max_words,
+            max_words: options.max_words,
             max_rank,
+            top_n: options.top_n,
             //words,
             //start_time,
             //end_time: None,
-            mnemonics: vec![]
-        };
-        let partial_mnemonic = Mnemonic {
-            phrase: "".to_string(),
-            word_count: 0,
-            score: NotNan::new(0.0).unwrap(),
+            mnemonics: vec![],
+            elapsed_build_btree,
+            elapsed_propose: Duration::default(),
         };
         let start_time_propose = Instant::now();
         let mut mnemonics = vec![];
-        run.propose_mnemonics(&words, &mut mnemonics, &partial_mnemonic, &number);
+        run.propose_mnemonics(word_list, options, &words, &mut mnemonics, &Mnemonic::new(), &number);
+        rank_mnemonics(&mut mnemonics, options.top_n);
         run.mnemonics = mnemonics;
-        let elapsed_propose = Instant::now() - start_time_propose;
-        let elapsed_overall = Instant::now() - start_time_overall;
-        dbg!(run.mnemonics.iter().map(|x| x.phrase.clone()).collect::<Vec<_>>());
-        dbg!(elapsed_build_btree, elapsed_propose, elapsed_overall);
+        run.elapsed_propose = Instant::now() - start_time_propose;
         run
     }
 
-    fn propose_mnemonics(&self, words: &BTreeMap<String, Vec<(String, usize)>>, mnemonics: &mut Vec<Mnemonic>, partial_mnemonic: &Mnemonic, remaining_number: &str) {
+    /// The best mnemonics found, best first.
+    pub fn mnemonics(&self) -> &[Mnemonic] {
+        &self.mnemonics
+    }
+
+    /// The results as a report. The run tries every split of the number rather than a set of
+    /// paths, so the report's paths are left empty.
+    pub fn report(&self) -> MnemonicReport {
+        MnemonicReport {
+            label: self.label.clone(),
+            number: self.number.clone(),
+            paths: vec![],
+            best: self.mnemonics.clone(),
+            elapsed: self.elapsed_build_btree + self.elapsed_propose,
+        }
+    }
+
+    fn propose_mnemonics(&self, word_list: &WordList, options: &MnemonicOptions, words: &WordsBTreeMap, mnemonics: &mut Vec<Mnemonic>, partial_mnemonic: &Mnemonic, remaining_number: &str) {
         //bg!(&partial_phrase, number);
         if partial_mnemonic.word_count < self.max_words {
             for length in (1..=remaining_number.len()).rev() {
                 let (match_number, new_remaining_number) = remaining_number.split_at(length);
                 //bg!(length, partial_number, remaining_number);
                 if let Some(matching_words) = words.get(match_number) {
-                    for (word, rank) in matching_words {
-                        let mut new_mnemonic = partial_mnemonic.clone();
-                        new_mnemonic.phrase = format!("{} {}", partial_mnemonic.phrase, word).trim().to_string();
-                        new_mnemonic.word_count += 1;
-                        new_mnemonic.score = new_mnemonic.score + NotNan::new(*rank as f64).unwrap();
+                    for (word, _rank) in matching_words {
+                        if let (Some(content_filter), Some(previous_word)) = (options.content_filter, partial_mnemonic.words.last()) {
+                            if content_filter.blocks_pair(previous_word, word) {
+                                continue;
+                            }
+                        }
+                        let breakdown = match score_word(word_list, options.scorer, word) {
+                            Some(breakdown) => breakdown,
+                            None => continue,
+                        };
+                        let new_mnemonic = partial_mnemonic.with_word(word, match_number, &breakdown);
                         if length == remaining_number.len() {
                             mnemonics.push(new_mnemonic);
                         } else {
-                            self.propose_mnemonics(words, mnemonics, &new_mnemonic, new_remaining_number);
+                            self.propose_mnemonics(word_list, options, words, mnemonics, &new_mnemonic, new_remaining_number);
                         }
                     }
                 }
@@ -98,83 +184,196 @@ impl MnemonicRun {
     }
 }
 
-pub fn propose_mnemonics_path_from_file(word_list: &WordList, file_name: &str, max_rank: usize) {
-    let words = gen_btreemap(word_list, max_rank);
-    for line in util_rust::parse::read_file_as_lines(file_name)
-            .iter()
-            .map(|line| line.trim())
-            .filter(|line| line.len() > 0 && !line.starts_with("#")) {
-        dbg!(&line);
-        let (label, match_numbers) = line.split_once("\t").unwrap();
-        propose_mnemonics_path(&words, label, match_numbers);
+pub fn propose_mnemonics_path_from_file<P: AsRef<Path>>(word_list: &WordList, path: P, max_rank: usize, options: &MnemonicOptions) -> Result<Vec<MnemonicReport>, LoadError> {
+    let mut index_options = IndexOptions { max_rank, ..IndexOptions::new() };
+    if let Some(content_filter) = options.content_filter {
+        content_filter.add_to_index_options(&mut index_options);
+    }
+    let words = gen_btreemap_with_options(word_list, &index_options);
+    let mut reports = vec![];
+    for (label, match_numbers) in load_numbers(path)? {
+        let number = input::detect(&match_numbers);
+        reports.push(propose_mnemonics_path_grouped(word_list, &words, &label, &number, options));
     }
+    Ok(reports)
 }
 
-pub fn propose_mnemonics_path(words: &WordsBTreeMap, label: &str, match_numbers: &str) -> String {
-    let start_time_overall = Instant::now();
+pub fn load_numbers<P: AsRef<Path>>(path: P) -> Result<Vec<(String, String)>, LoadError> {
+    let file_name = path.as_ref().display().to_string();
+    let file = File::open(path.as_ref()).map_err(|e| LoadError::io(&file_name, e))?;
+    numbers_from_reader(BufReader::new(file), &file_name)
+}
+
+/// Reads lines of the form "label<tab>number". Blank lines and lines starting with "#" are
+/// skipped.
+pub fn numbers_from_reader<R: BufRead>(reader: R, source_name: &str) -> Result<Vec<(String, String)>, LoadError> {
+    let mut numbers = vec![];
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| LoadError::io(source_name, e))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("#") {
+            continue;
+        }
+        let (label, match_numbers) = line.split_once("\t")
+            .ok_or_else(|| LoadError::missing_field(source_name, index + 1, "number"))?;
+        if parse::digits_only(match_numbers).is_empty() {
+            return Err(LoadError::invalid_value(source_name, index + 1, "number", match_numbers));
+        }
+        numbers.push((label.trim().to_string(), match_numbers.trim().to_string()));
+    }
+    Ok(numbers)
+}
 
-    let display_width = 100;
+/// Finds the segmentations of the number into the fewest words, the words that fit each one and
+/// the best top_n phrases across all of them. If a content filter is given, phrases with a
+/// blocked pair of neighbouring words are skipped.
+pub fn propose_mnemonics_path(word_list: &WordList, words: &WordsBTreeMap, label: &str, match_numbers: &str, options: &MnemonicOptions) -> MnemonicReport {
+    propose_mnemonics_path_with_breaks(word_list, words, label, &parse::digits_only(match_numbers), &[], options)
+}
 
-    let mut report = String::new();
-    report.push_str(&format::header(0, label, display_width));
+/// Like propose_mnemonics_path() but for a number with natural groups, like the parts of a date
+/// or phone number. Only segmentations that keep every group boundary are used, so each word
+/// stays inside one group and the phrase reads in the same rhythm as the number. If no such
+/// segmentation has words for it, this falls back to ignoring the groups.
+pub fn propose_mnemonics_path_grouped(word_list: &WordList, words: &WordsBTreeMap, label: &str, number: &ParsedNumber, options: &MnemonicOptions) -> MnemonicReport {
+    let digits = number.digits();
+    let report = propose_mnemonics_path_with_breaks(word_list, words, label, &digits, &number.breaks(), options);
+    if report.paths.is_empty() && number.groups.len() > 1 {
+        propose_mnemonics_path_with_breaks(word_list, words, label, &digits, &[], options)
+    } else {
+        report
+    }
+}
 
-    let match_numbers = parse::digits_only(match_numbers);
+// Breaks are offsets into match_numbers that every path has to split at.
+fn propose_mnemonics_path_with_breaks(word_list: &WordList, words: &WordsBTreeMap, label: &str, match_numbers: &str, breaks: &[usize], options: &MnemonicOptions) -> MnemonicReport {
+    let start_time = Instant::now();
 
-    let start_time_propose = Instant::now();
+    let match_numbers = match_numbers.to_string();
 
     // Try to find mnemonics with the least possible number of words.
     // The most possible words is the length of the match number, with a single phone per word.
-    let mut found = false;
-    for path_length in 1..=match_numbers.len() {
-        let paths = gen_paths(&match_numbers, path_length);
+    let mut paths = vec![];
+    let mut best = vec![];
+    for path_length in 1..=match_numbers.len().min(options.max_words) {
         //bg!(path_length, &paths);
-        for path in paths.iter() {
+        for path in gen_paths(&match_numbers, path_length) {
             // See if we have at least one matching word for each step in the path.
-            if path.iter().all(|key| words.contains_key(key)) {
-                found = true;
-                //rintln!("\n\n{}", path.iter().join("-"));
-                report.push_str(&format::header(1,&path.iter().join("-"), display_width));
-                for (index, key) in path.iter().enumerate() {
-                    let found_words = words.get(key).unwrap().iter().map(|(word, _)| word).join(" ");
-                    //rintln!("\t{}", found_words);
-                    //rintln!("\n{}", format::wrap_hanging_indent(&found_words, "", 1, 100));
-                    //report.push_str(&format!("\n{}", format::wrap_hanging_indent(&found_words, "", 1, 100)));
-                    report.push_str(&format!("\n{}", &found_words));
-
-                    if index == path.len() - 1 {
-                        // This is the last entry in the path so propose longer words that contain
-                        // phones that would be ignored for the mnemonic. This is only useful when
-                        // the match number is something like a PIN or phone number where we know
-                        // the length in advance.
-                        let extra_keys = words
-                            .keys()
-                            .filter(|extra_key| extra_key.len() > key.len() && extra_key.starts_with(key))
-                            .map(|extra_key| extra_key.to_string())
-                            .collect::<Vec<_>>();
-                        //bg!(&extra_keys);
-                        let mut extra_words = extra_keys
-                            .iter()
-                            .map(|extra_key| words.get(extra_key).unwrap().iter().map(|(word, _)| word))
-                            .flatten()
-                            .collect::<Vec<_>>();
-                        //bg!(&extra_words);
-                        extra_words.sort();
-                        let extra_words = format!("[[[ {} ]]]", extra_words.iter().join(" "));
-                        report.push_str(&format!("\n{}", &extra_words));
-                    }
-                }
+            if keeps_breaks(&path, breaks) && path.iter().all(|key| words.contains_key(key)) {
+                best.extend(best_mnemonics_for_path(word_list, words, &path, options));
+                let choices = path
+                    .iter()
+                    .map(|key| word_choices(word_list, key, &words[key]))
+                    .collect();
+                // Propose longer words for the last entry in the path that contain phones that
+                // would be ignored for the mnemonic. This is only useful when the match number is
+                // something like a PIN or phone number where we know the length in advance.
+                // The keys are sorted so the ones starting with this key come right after it.
+                let key = path.last().unwrap();
+                let mut extra = words
+                    .range::<String, _>((Excluded(key), Unbounded))
+                    .take_while(|(extra_key, _)| extra_key.starts_with(key.as_str()))
+                    .flat_map(|(extra_key, extra_words)| word_choices(word_list, extra_key, extra_words))
+                    .collect::<Vec<_>>();
+                extra.sort_by(|a, b| a.word.cmp(&b.word));
+                paths.push(PathCandidate {
+                    segments: path,
+                    choices,
+                    extra,
+                });
             }
         }
-        if found {
+        if !paths.is_empty() {
             break;
         }
     }
+    rank_mnemonics(&mut best, options.top_n);
+
+    MnemonicReport {
+        label: label.to_string(),
+        number: match_numbers,
+        paths,
+        best,
+        elapsed: Instant::now() - start_time,
+    }
+}
+
+// Whether the path splits the number at every break.
+fn keeps_breaks(path: &[String], breaks: &[usize]) -> bool {
+    let ends = path.iter()
+        .scan(0, |offset, segment| {
+            *offset += segment.len();
+            Some(*offset)
+        })
+        .collect::<Vec<_>>();
+    breaks.iter().all(|offset| ends.contains(offset))
+}
+
+fn word_choices(word_list: &WordList, code: &str, words: &[(String, usize)]) -> Vec<WordChoice> {
+    words
+        .iter()
+        .map(|(word, rank)| WordChoice {
+            word: word.clone(),
+            code: code.to_string(),
+            rank: *rank,
+            frequency: word_list.words.get(&word.to_lowercase()).map_or(0, |entry| entry.frequency),
+        })
+        .collect()
+}
+
+// The best phrases for one segmentation of the number. Since a phrase's score is the sum of its
+// words' scores, without a content filter the best top_n phrases can only use each segment's best
+// top_n words. With one, whether a word can follow a phrase depends on the phrase's last word, so
+// instead the best top_n phrases ending in each word are kept at every step.
+fn best_mnemonics_for_path(word_list: &WordList, words: &WordsBTreeMap, path: &[String], options: &MnemonicOptions) -> Vec<Mnemonic> {
+    let top_n = options.top_n;
+    let mut mnemonics = vec![Mnemonic::new()];
+    for key in path.iter() {
+        let mut candidates = words[key]
+            .iter()
+            .filter(|(word, _)| options.content_filter.is_none_or(|content_filter| !content_filter.blocks_word(word)))
+            .filter_map(|(word, _)| score_word(word_list, options.scorer, word).map(|breakdown| (word, breakdown)))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, breakdown)| NotNan::new(breakdown.total()).unwrap());
+        match options.content_filter {
+            None => {
+                candidates.truncate(top_n);
+                mnemonics = mnemonics
+                    .iter()
+                    .cartesian_product(candidates.iter())
+                    .map(|(mnemonic, (word, breakdown))| mnemonic.with_word(word, key, breakdown))
+                    .collect();
+                rank_mnemonics(&mut mnemonics, top_n);
+            },
+            Some(content_filter) => {
+                // The phrases so far are best first, so the first top_n that allow the word are
+                // the best ones.
+                mnemonics = candidates
+                    .iter()
+                    .flat_map(|(word, breakdown)| mnemonics
+                        .iter()
+                        .filter(|mnemonic| mnemonic.words.last().is_none_or(|previous_word| !content_filter.blocks_pair(previous_word, word)))
+                        .take(top_n)
+                        .map(|mnemonic| mnemonic.with_word(word, key, breakdown))
+                        .collect::<Vec<_>>())
+                    .collect();
+                rank_mnemonics(&mut mnemonics, usize::MAX);
+            },
+        }
+    }
+    rank_mnemonics(&mut mnemonics, top_n);
+    mnemonics
+}
+
+fn score_word(word_list: &WordList, scorer: &dyn Scorer, word: &str) -> Option<ScoreBreakdown> {
+    word_list.words.get(&word.to_lowercase()).map(|word| scorer.score_word(word))
+}
 
-    let _elapsed_propose = Instant::now() - start_time_propose;
-    let _elapsed_overall = Instant::now() - start_time_overall;
-    //bg!(elapsed_build_btree, elapsed_propose, elapsed_overall);
-    println!("{}", report.replace("\n\n", "\n"));
-    report
+// Sorts best first, drops repeated phrases and keeps the best top_n.
+fn rank_mnemonics(mnemonics: &mut Vec<Mnemonic>, top_n: usize) {
+    mnemonics.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.phrase.cmp(&b.phrase)));
+    mnemonics.dedup_by(|a, b| a.phrase == b.phrase);
+    mnemonics.truncate(top_n);
 }
 
 fn gen_paths(match_numbers: &str, path_length: usize) -> Vec<Vec<String>> {
@@ -210,18 +409,64 @@ fn gen_path_internal(paths: &mut Vec<Vec<String>>, partial_path: Vec<String>, re
             //bg!(length, these_match_numbers, new_remaining_match_numbers);
             let mut new_path = partial_path.clone();
             new_path.push(these_match_numbers.to_string());
-            gen_path_internal(paths, new_path, &new_remaining_match_numbers, remaining_path_length - 1);
+            gen_path_internal(paths, new_path, new_remaining_match_numbers, remaining_path_length - 1);
+        }
+    }
+}
+
+impl IndexOptions {
+    pub fn new() -> Self {
+        Self {
+            max_rank: usize::MAX,
+            min_concreteness: None,
+            always_include: BTreeSet::new(),
+            blocked: BTreeSet::new(),
+            pegs: BTreeMap::new(),
+        }
+    }
+
+    pub fn includes(&self, word: &Word) -> bool {
+        let key = word.word.to_lowercase();
+        if self.blocked.contains(&key) {
+            return false;
         }
+        if self.always_include.contains(&key) {
+            return true;
+        }
+        word.rank <= self.max_rank
+            && self.min_concreteness.is_none_or(|min_concreteness| word.concreteness.is_some_and(|concreteness| concreteness >= min_concreteness))
+    }
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 pub fn gen_btreemap(word_list: &WordList, max_rank: usize) -> BTreeMap<String, Vec<(String, usize)>>{
+    gen_btreemap_with_options(word_list, &IndexOptions { max_rank, ..IndexOptions::new() })
+}
+
+pub fn gen_btreemap_with_options(word_list: &WordList, options: &IndexOptions) -> WordsBTreeMap {
     // One entry per mnemonic with multiple words possible per entry.
     //let start_time_build_btree = Instant::now();
     let mut map = BTreeMap::new();
-    for word in word_list.words.values().filter(|word| word.mnemonic.as_ref().is_some() && word.rank <= max_rank) {
-        let entry = map.entry(word.mnemonic.as_ref().unwrap().clone()).or_insert(vec![]);
-        entry.push((word.word.clone(), word.rank));
+    for word in word_list.words.values().filter(|word| word.has_mnemonic() && options.includes(word)) {
+        for mnemonic in word.mnemonics.iter().filter(|mnemonic| !options.pegs.contains_key(*mnemonic)) {
+            let entry = map.entry(mnemonic.clone()).or_insert(vec![]);
+            entry.push((word.word.clone(), word.rank));
+        }
+    }
+    for (code, peg_words) in options.pegs.iter() {
+        let entry = peg_words.iter()
+            .filter_map(|peg_word| word_list.words.get(peg_word))
+            .filter(|word| options.includes(word))
+            .map(|word| (word.word.clone(), word.rank))
+            .collect::<Vec<_>>();
+        if !entry.is_empty() {
+            map.insert(code.clone(), entry);
+        }
     }
     //let _elapsed_build_btree = Instant::now() - start_time_build_btree;
     map
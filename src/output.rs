@@ -0,0 +1,180 @@
+use crate::itertools::Itertools;
+use crate::report::{MnemonicReport, WordChoice};
+use crate::util::format;
+
+const DISPLAY_WIDTH: usize = 100;
+
+/// The plain text layout that propose_mnemonics_path() used to print: a header per path, the
+/// words for each segment on a line, the extra words for the last segment in [[[ ]]], and then
+/// the best phrases with their scores.
+pub fn format_text(report: &MnemonicReport) -> String {
+    let mut text = String::new();
+    text.push_str(&format::header(0, &report.label, DISPLAY_WIDTH));
+    for path in report.paths.iter() {
+        text.push_str(&format::header(1, &path.segmentation(), DISPLAY_WIDTH));
+        for choices in path.choices.iter() {
+            text.push_str(&format!("\n{}", choices.iter().map(|choice| &choice.word).join(" ")));
+        }
+        text.push_str(&format!("\n[[[ {} ]]]", path.extra.iter().map(|choice| &choice.word).join(" ")));
+    }
+    if !report.best.is_empty() {
+        text.push_str(&format::header(1, "Best", DISPLAY_WIDTH));
+        for mnemonic in report.best.iter() {
+            text.push_str(&format!("\n{}\t{:.2}", mnemonic.phrase, mnemonic.score));
+        }
+    }
+    text.replace("\n\n", "\n")
+}
+
+/// The ways a list of reports can be written out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    /// A single JSON array with one object per report.
+    Json,
+    /// One JSON object per report, one per line.
+    JsonLines,
+    /// One row per candidate phrase.
+    Csv,
+    /// A table of candidate phrases per report.
+    Markdown,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" | "json-lines" => Ok(OutputFormat::JsonLines),
+            "csv" => Ok(OutputFormat::Csv),
+            "md" | "markdown" => Ok(OutputFormat::Markdown),
+            _ => Err(format!("Unknown format \"{}\". Expected text, json, jsonl, csv or markdown.", name)),
+        }
+    }
+}
+
+pub fn render(reports: &[MnemonicReport], output_format: OutputFormat) -> String {
+    match output_format {
+        OutputFormat::Text => reports.iter().map(format_text).join("\n"),
+        OutputFormat::Json => format!("[{}]", reports.iter().map(format_json).join(",")),
+        OutputFormat::JsonLines => reports.iter().map(|report| format!("{}\n", format_json(report))).join(""),
+        OutputFormat::Csv => format_csv(reports),
+        OutputFormat::Markdown => reports.iter().map(format_markdown).join("\n"),
+    }
+}
+
+pub fn format_json(report: &MnemonicReport) -> String {
+    let paths = report.paths.iter().map(|path| {
+        let choices = path.choices.iter().map(|choices| json_array(choices.iter().map(json_word_choice))).collect::<Vec<_>>();
+        format!("{{\"segments\":{},\"choices\":{},\"extra\":{}}}",
+            json_array(path.segments.iter().map(|segment| json_string(segment))),
+            json_array(choices.into_iter()),
+            json_array(path.extra.iter().map(json_word_choice)))
+    });
+    let best = report.best.iter().map(|mnemonic| {
+        format!("{{\"phrase\":{},\"words\":{},\"codes\":{},\"segmentation\":{},\"score\":{},\"breakdown\":{{\"word_count\":{},\"frequency\":{},\"dispersion\":{},\"part_of_speech\":{},\"concreteness\":{},\"segmentation\":{}}}}}",
+            json_string(&mnemonic.phrase),
+            json_array(mnemonic.words.iter().map(|word| json_string(word))),
+            json_array(mnemonic.codes.iter().map(|code| json_string(code))),
+            json_string(&mnemonic.codes.join("-")),
+            json_number(mnemonic.score.into_inner()),
+            json_number(mnemonic.breakdown.word_count),
+            json_number(mnemonic.breakdown.frequency),
+            json_number(mnemonic.breakdown.dispersion),
+            json_number(mnemonic.breakdown.part_of_speech),
+            json_number(mnemonic.breakdown.concreteness),
+            json_number(mnemonic.breakdown.segmentation))
+    });
+    format!("{{\"label\":{},\"number\":{},\"elapsed_ms\":{},\"paths\":{},\"best\":{}}}",
+        json_string(&report.label),
+        json_string(&report.number),
+        json_number(report.elapsed.as_secs_f64() * 1_000.0),
+        json_array(paths),
+        json_array(best))
+}
+
+/// A header row and then one row per candidate phrase. Reports with no phrases get a single row
+/// with the phrase columns left empty so that every input number appears.
+pub fn format_csv(reports: &[MnemonicReport]) -> String {
+    let mut csv = String::from("label,number,position,phrase,segmentation,score\n");
+    for report in reports.iter() {
+        if report.best.is_empty() {
+            csv.push_str(&format!("{},{},,,,\n", csv_field(&report.label), csv_field(&report.number)));
+        }
+        for (index, mnemonic) in report.best.iter().enumerate() {
+            csv.push_str(&format!("{},{},{},{},{},{:.2}\n",
+                csv_field(&report.label),
+                csv_field(&report.number),
+                index + 1,
+                csv_field(&mnemonic.phrase),
+                csv_field(&mnemonic.codes.join("-")),
+                mnemonic.score));
+        }
+    }
+    csv
+}
+
+pub fn format_markdown(report: &MnemonicReport) -> String {
+    let mut markdown = format!("## {} ({})\n\n", markdown_cell(&report.label), report.number);
+    if report.best.is_empty() {
+        markdown.push_str("No mnemonics found.\n");
+        return markdown;
+    }
+    markdown.push_str("| # | Phrase | Segmentation | Score |\n");
+    markdown.push_str("|---|--------|--------------|-------|\n");
+    for (index, mnemonic) in report.best.iter().enumerate() {
+        markdown.push_str(&format!("| {} | {} | {} | {:.2} |\n",
+            index + 1,
+            markdown_cell(&mnemonic.phrase),
+            mnemonic.codes.join("-"),
+            mnemonic.score));
+    }
+    markdown
+}
+
+fn json_word_choice(choice: &WordChoice) -> String {
+    format!("{{\"word\":{},\"code\":{},\"rank\":{},\"frequency\":{}}}",
+        json_string(&choice.word), json_string(&choice.code), choice.rank, choice.frequency)
+}
+
+fn json_array<I: Iterator<Item = String>>(items: I) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+// JSON has no NaN or infinity so those become null.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{}", (value * 1_000.0).round() / 1_000.0)
+    } else {
+        "null".to_string()
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
@@ -0,0 +1,324 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use ordered_float::NotNan;
+use crate::error::LoadError;
+use crate::mnemonic::WordsBTreeMap;
+use crate::report::WordChoice;
+use crate::score::Scorer;
+use crate::words::{Word, WordList};
+use crate::util::parse;
+
+pub const PAO_FILE_NAME: &str = "PAO Table.txt";
+
+const TABLE_HEADER: &str = "code\tperson\taction\tobject";
+
+// Digits per role and per sentence.
+const CODE_WIDTH: usize = 2;
+const CHUNK_WIDTH: usize = 6;
+
+/// The three parts of a Person-Action-Object sentence. A six-digit chunk like 071425 becomes the
+/// person for 07 doing the action for 14 to the object for 25.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PaoRole {
+    Person,
+    Action,
+    Object,
+}
+
+/// The words that could fill each role for one two-digit code, best first.
+#[derive(Clone, Debug)]
+pub struct PaoCandidates {
+    pub code: String,
+    pub persons: Vec<WordChoice>,
+    pub actions: Vec<WordChoice>,
+    pub objects: Vec<WordChoice>,
+}
+
+/// The chosen person, action and object for one code. A role is None until something is chosen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PaoEntry {
+    pub person: Option<String>,
+    pub action: Option<String>,
+    pub object: Option<String>,
+}
+
+/// A person, action and object for each code from 00 to 99. Saved as a TSV file so that it's
+/// easy to review and edit by hand.
+#[derive(Clone, Debug, Default)]
+pub struct PaoTable {
+    // Keyed by two-digit code.
+    pub entries: BTreeMap<String, PaoEntry>,
+}
+
+/// One chunk of a number as a PAO sentence. A chunk at the end of the number with fewer than six
+/// digits leaves off the later roles.
+#[derive(Clone, Debug)]
+pub struct PaoSentence {
+    pub chunk: String,
+    pub person: String,
+    pub action: Option<String>,
+    pub object: Option<String>,
+}
+
+impl PaoRole {
+    pub const ALL: [PaoRole; 3] = [PaoRole::Person, PaoRole::Action, PaoRole::Object];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaoRole::Person => "person",
+            PaoRole::Action => "action",
+            PaoRole::Object => "object",
+        }
+    }
+
+    /// Whether a word can play this role. Persons are proper nouns, tagged "np" in the word list
+    /// or listed under [persons] in the user dictionary. Capitalization doesn't count since some
+    /// languages capitalize every noun. Actions are verbs or -ing forms, and objects are common
+    /// nouns.
+    pub fn fits(&self, word: &Word) -> bool {
+        let part_of_speech = word.part_of_speech.trim().to_lowercase();
+        let proper_noun = part_of_speech.starts_with("np");
+        match self {
+            PaoRole::Person => proper_noun,
+            PaoRole::Action => part_of_speech == "v" || word.word.to_lowercase().ends_with("ing"),
+            PaoRole::Object => part_of_speech == "n" && !proper_noun,
+        }
+    }
+}
+
+impl fmt::Display for PaoRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl PaoCandidates {
+    pub fn for_role(&self, role: PaoRole) -> &[WordChoice] {
+        match role {
+            PaoRole::Person => &self.persons,
+            PaoRole::Action => &self.actions,
+            PaoRole::Object => &self.objects,
+        }
+    }
+}
+
+impl PaoEntry {
+    pub fn get(&self, role: PaoRole) -> Option<&str> {
+        match role {
+            PaoRole::Person => self.person.as_deref(),
+            PaoRole::Action => self.action.as_deref(),
+            PaoRole::Object => self.object.as_deref(),
+        }
+    }
+
+    pub fn set(&mut self, role: PaoRole, word: Option<String>) {
+        match role {
+            PaoRole::Person => self.person = word,
+            PaoRole::Action => self.action = word,
+            PaoRole::Object => self.object = word,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        PaoRole::ALL.iter().all(|role| self.get(*role).is_some())
+    }
+}
+
+impl PaoTable {
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Fills every role that hasn't been chosen yet with the best candidate, leaving roles that
+    /// are already set alone. One word is never used for two roles or two codes.
+    pub fn fill(&mut self, candidates: &[PaoCandidates]) {
+        let mut used = self.entries.values()
+            .flat_map(|entry| PaoRole::ALL.iter().filter_map(move |role| entry.get(*role)))
+            .map(str::to_lowercase)
+            .collect::<BTreeSet<_>>();
+        for code_candidates in candidates.iter() {
+            let entry = self.entries.entry(code_candidates.code.clone()).or_default();
+            for role in PaoRole::ALL.iter() {
+                if entry.get(*role).is_some() {
+                    continue;
+                }
+                let choice = code_candidates.for_role(*role).iter().find(|choice| !used.contains(&choice.word.to_lowercase()));
+                if let Some(choice) = choice {
+                    used.insert(choice.word.to_lowercase());
+                    entry.set(*role, Some(choice.word.clone()));
+                }
+            }
+        }
+    }
+
+    /// The codes from 00 to 99 that are missing at least one role.
+    pub fn incomplete_codes(&self) -> Vec<String> {
+        all_codes()
+            .filter(|code| !self.entries.get(code).is_some_and(PaoEntry::is_complete))
+            .collect()
+    }
+
+    /// Loads a table, or an empty one if the file doesn't exist yet.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        match File::open(path.as_ref()) {
+            Ok(file) => Self::from_reader(BufReader::new(file), &file_name),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(LoadError::io(&file_name, e)),
+        }
+    }
+
+    /// Reads "code<tab>person<tab>action<tab>object" lines after a header. Blank roles are left
+    /// unchosen.
+    pub fn from_reader<R: BufRead>(reader: R, source_name: &str) -> Result<Self, LoadError> {
+        let mut table = Self::new();
+        for (index, line) in reader.lines().enumerate().skip(1) {
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_number = index + 1;
+            let fields = line.split('\t').map(str::trim).collect::<Vec<_>>();
+            let code = fields[0];
+            if code.len() != CODE_WIDTH || !code.chars().all(|c| c.is_ascii_digit()) {
+                return Err(LoadError::invalid_value(source_name, line_number, "code", code));
+            }
+            let mut entry = PaoEntry::default();
+            for (field_index, role) in PaoRole::ALL.iter().enumerate() {
+                let word = fields.get(field_index + 1).copied().unwrap_or("");
+                entry.set(*role, Some(word.to_string()).filter(|word| !word.is_empty()));
+            }
+            table.entries.insert(code.to_string(), entry);
+        }
+        Ok(table)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let mut text = format!("{}\n", TABLE_HEADER);
+        for code in all_codes() {
+            let entry = self.entries.get(&code).cloned().unwrap_or_default();
+            text.push_str(&format!("{}\t{}\t{}\t{}\n",
+                code,
+                entry.person.unwrap_or_default(),
+                entry.action.unwrap_or_default(),
+                entry.object.unwrap_or_default()));
+        }
+        fs::write(&path, text).map_err(|e| format!("Unable to write \"{}\": {}", path.as_ref().display(), e))
+    }
+
+    /// One sentence per six digits of the number. The number needs an even number of digits so
+    /// that every role gets a whole code.
+    pub fn encode(&self, number: &str) -> Result<Vec<PaoSentence>, String> {
+        let digits = parse::digits_only(number);
+        if digits.is_empty() || !digits.len().is_multiple_of(CODE_WIDTH) {
+            return Err(format!("A PAO number needs an even number of digits but \"{}\" has {}.", number, digits.len()));
+        }
+        digits.as_bytes()
+            .chunks(CHUNK_WIDTH)
+            .map(|chunk| self.encode_chunk(std::str::from_utf8(chunk).unwrap()))
+            .collect()
+    }
+
+    /// The sentence for up to six digits.
+    pub fn encode_chunk(&self, chunk: &str) -> Result<PaoSentence, String> {
+        let mut words = vec![];
+        for (role, start) in PaoRole::ALL.iter().zip((0..chunk.len()).step_by(CODE_WIDTH)) {
+            let code = &chunk[start..(start + CODE_WIDTH).min(chunk.len())];
+            let word = self.entries.get(code)
+                .and_then(|entry| entry.get(*role))
+                .ok_or_else(|| format!("The PAO table has no {} for {}.", role, code))?;
+            words.push(word.to_string());
+        }
+        let mut words = words.into_iter();
+        Ok(PaoSentence {
+            chunk: chunk.to_string(),
+            person: words.next().unwrap(),
+            action: words.next(),
+            object: words.next(),
+        })
+    }
+}
+
+impl fmt::Display for PaoSentence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.person)?;
+        for word in self.action.iter().chain(self.object.iter()) {
+            write!(f, " {}", word)?;
+        }
+        Ok(())
+    }
+}
+
+/// The best top_n words for each role for every code from 00 to 99, taken from an index made by
+/// gen_btreemap() so that the usual rank limits, pegs and blocked words apply.
+pub fn propose_candidates(word_list: &WordList, words: &WordsBTreeMap, scorer: &dyn Scorer, top_n: usize) -> Vec<PaoCandidates> {
+    all_codes()
+        .map(|code| {
+            let code_words = words.get(&code).map_or(&[][..], |code_words| code_words.as_slice());
+            let role_candidates = |role: PaoRole| {
+                let mut choices = code_words.iter()
+                    .filter_map(|(word, rank)| word_list.words.get(&word.to_lowercase()).map(|entry| (word, rank, entry)))
+                    .filter(|(_, _, entry)| role.fits(entry))
+                    .map(|(word, rank, entry)| (NotNan::new(scorer.score_word(entry).total()).unwrap(), WordChoice {
+                        word: word.clone(),
+                        code: code.clone(),
+                        rank: *rank,
+                        frequency: entry.frequency,
+                    }))
+                    .collect::<Vec<_>>();
+                choices.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.word.cmp(&b.1.word)));
+                choices.into_iter().take(top_n).map(|(_, choice)| choice).collect::<Vec<_>>()
+            };
+            PaoCandidates {
+                persons: role_candidates(PaoRole::Person),
+                actions: role_candidates(PaoRole::Action),
+                objects: role_candidates(PaoRole::Object),
+                code,
+            }
+        })
+        .collect()
+}
+
+fn all_codes() -> impl Iterator<Item = String> {
+    (0..100).map(|code| format!("{:02}", code))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use crate::words::Word;
+    use super::PaoRole;
+
+    fn word(word: &str, part_of_speech: &str) -> Word {
+        Word {
+            word: word.to_string(),
+            rank: 1,
+            frequency: 0,
+            dispersion: 1.0,
+            part_of_speech: part_of_speech.to_string(),
+            mnemonics: BTreeSet::new(),
+            guessed: false,
+            concreteness: None,
+        }
+    }
+
+    fn roles(word: &Word) -> Vec<PaoRole> {
+        PaoRole::ALL.iter().copied().filter(|role| role.fits(word)).collect()
+    }
+
+    #[test]
+    fn persons_are_tagged_not_capitalized() {
+        assert_eq!(roles(&word("Nemo", "np")), vec![PaoRole::Person]);
+        assert_eq!(roles(&word("nemo", "NP1")), vec![PaoRole::Person]);
+        // German capitalizes every noun.
+        assert_eq!(roles(&word("Hund", "n")), vec![PaoRole::Object]);
+        assert_eq!(roles(&word("Tie", "v")), vec![PaoRole::Action]);
+        assert_eq!(roles(&word("singing", "n")), vec![PaoRole::Action, PaoRole::Object]);
+        assert!(roles(&word("the", "a")).is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use ordered_float::NotNan;
+use crate::mnemonic::WordsBTreeMap;
+use crate::report::WordChoice;
+use crate::score::Scorer;
+use crate::words::WordList;
+use crate::util::format;
+
+const DISPLAY_WIDTH: usize = 100;
+
+/// How good the best word for a code is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PegStatus {
+    Good,
+    /// Every candidate is rarer than PegOptions::weak_rank.
+    Weak,
+    /// No word in the index has this code.
+    Missing,
+}
+
+#[derive(Clone, Debug)]
+pub struct PegOptions {
+    // The code lengths to cover. 1, 2 and 3 give 0-9, 00-99 and 000-999.
+    pub lengths: Vec<usize>,
+    pub top_n: usize,
+    // A code is weak if it has no candidate this common or more.
+    pub weak_rank: usize,
+}
+
+/// The candidate pegs for one code, best first by the scorer.
+#[derive(Clone, Debug)]
+pub struct PegEntry {
+    pub code: String,
+    pub candidates: Vec<WordChoice>,
+    pub status: PegStatus,
+}
+
+/// How many codes of one length have good, weak or no pegs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PegCoverage {
+    pub length: usize,
+    pub good: usize,
+    pub weak: usize,
+    pub missing: usize,
+}
+
+impl PegStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PegStatus::Good => "good",
+            PegStatus::Weak => "weak",
+            PegStatus::Missing => "missing",
+        }
+    }
+}
+
+impl fmt::Display for PegStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl PegOptions {
+    pub fn new() -> Self {
+        Self {
+            lengths: vec![1, 2, 3],
+            top_n: 5,
+            weak_rank: 2_000,
+        }
+    }
+}
+
+impl Default for PegOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PegEntry {
+    pub fn best(&self) -> Option<&WordChoice> {
+        self.candidates.first()
+    }
+}
+
+impl PegCoverage {
+    pub fn total(&self) -> usize {
+        self.good + self.weak + self.missing
+    }
+}
+
+/// Every code of each length in the options with its best top_n words. The index should come
+/// from gen_btreemap() with the max_rank to check, so that only words within it count.
+pub fn peg_table(word_list: &WordList, words: &WordsBTreeMap, scorer: &dyn Scorer, options: &PegOptions) -> Vec<PegEntry> {
+    let mut entries = vec![];
+    for &length in options.lengths.iter() {
+        for code in 0..10_usize.pow(length as u32) {
+            let code = format!("{:0width$}", code, width = length);
+            let mut candidates = words.get(&code).map_or(vec![], |code_words| {
+                code_words.iter()
+                    .filter_map(|(word, rank)| word_list.words.get(&word.to_lowercase()).map(|entry| (NotNan::new(scorer.score_word(entry).total()).unwrap(), WordChoice {
+                        word: word.clone(),
+                        code: code.clone(),
+                        rank: *rank,
+                        frequency: entry.frequency,
+                    })))
+                    .collect::<Vec<_>>()
+            });
+            candidates.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.word.cmp(&b.1.word)));
+            let status = if candidates.is_empty() {
+                PegStatus::Missing
+            } else if candidates.iter().all(|(_, choice)| choice.rank > options.weak_rank) {
+                PegStatus::Weak
+            } else {
+                PegStatus::Good
+            };
+            entries.push(PegEntry {
+                code,
+                candidates: candidates.into_iter().take(options.top_n).map(|(_, choice)| choice).collect(),
+                status,
+            });
+        }
+    }
+    entries
+}
+
+/// A count of good, weak and missing codes for each code length, shortest first.
+pub fn coverage(entries: &[PegEntry]) -> Vec<PegCoverage> {
+    let mut coverage: Vec<PegCoverage> = vec![];
+    for entry in entries.iter() {
+        let length = entry.code.len();
+        let index = match coverage.iter().position(|length_coverage| length_coverage.length == length) {
+            Some(index) => index,
+            None => {
+                coverage.push(PegCoverage { length, ..Default::default() });
+                coverage.len() - 1
+            },
+        };
+        match entry.status {
+            PegStatus::Good => coverage[index].good += 1,
+            PegStatus::Weak => coverage[index].weak += 1,
+            PegStatus::Missing => coverage[index].missing += 1,
+        }
+    }
+    coverage.sort_by_key(|length_coverage| length_coverage.length);
+    coverage
+}
+
+/// A section per code length with a line per code, weak and missing codes flagged, and then the
+/// coverage counts.
+pub fn format_text(entries: &[PegEntry]) -> String {
+    let mut text = String::new();
+    let mut length = 0;
+    for entry in entries.iter() {
+        if entry.code.len() != length {
+            length = entry.code.len();
+            text.push_str(&format::header(0, &format!("{}-digit codes", length), DISPLAY_WIDTH));
+        }
+        let flag = match entry.status {
+            PegStatus::Good => "",
+            PegStatus::Weak => "  (weak)",
+            PegStatus::Missing => "  (missing)",
+        };
+        text.push_str(&format!("\n{}\t{}{}", entry.code, entry.candidates.iter().map(|choice| choice.word.as_str()).collect::<Vec<_>>().join(" "), flag));
+    }
+    text.push_str(&format::header(0, "Coverage", DISPLAY_WIDTH));
+    for length_coverage in coverage(entries).iter() {
+        text.push_str(&format!("\n{}-digit codes: {} good, {} weak, {} missing of {}",
+            length_coverage.length, length_coverage.good, length_coverage.weak, length_coverage.missing, length_coverage.total()));
+    }
+    text.replace("\n\n", "\n")
+}
+
+/// A row per code with its status, best peg and the other candidates, for printing or a
+/// spreadsheet.
+pub fn format_tsv(entries: &[PegEntry]) -> String {
+    let mut tsv = "code\tstatus\tpeg\talternatives\n".to_string();
+    for entry in entries.iter() {
+        tsv.push_str(&format!("{}\t{}\t{}\t{}\n",
+            entry.code,
+            entry.status,
+            entry.best().map_or("", |choice| choice.word.as_str()),
+            entry.candidates.iter().skip(1).map(|choice| choice.word.as_str()).collect::<Vec<_>>().join(", ")));
+    }
+    tsv
+}
+
+/// A [pegs] section for a user dictionary with the best word for each code that has one. Weak
+/// pegs are marked with a comment so they're easy to find and replace.
+pub fn format_user_dictionary(entries: &[PegEntry]) -> String {
+    let mut text = "[pegs]\n".to_string();
+    for entry in entries.iter() {
+        if let Some(best) = entry.best() {
+            let comment = if entry.status == PegStatus::Weak { "  # weak" } else { "" };
+            text.push_str(&format!("{} = {}{}\n", entry.code, best.word, comment));
+        }
+    }
+    for entry in entries.iter().filter(|entry| entry.status == PegStatus::Missing) {
+        text.push_str(&format!("# {} = \n", entry.code));
+    }
+    text
+}
+
+/// Writes the peg table as TSV if the file name ends in .tsv, and otherwise as a user dictionary.
+pub fn save<P: AsRef<Path>>(entries: &[PegEntry], path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    let text = if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("tsv")) {
+        format_tsv(entries)
+    } else {
+        format_user_dictionary(entries)
+    };
+    fs::write(path, text).map_err(|e| format!("Unable to write \"{}\": {}", path.display(), e))
+}
@@ -0,0 +1,388 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::LoadError;
+use crate::encoding::{self, Encoding};
+use crate::words::{self, PronunciationMap};
+use crate::util::parse;
+
+pub const PRACTICE_FILE_NAME: &str = "Practice.txt";
+
+const STATE_HEADER: &str = "label\tnumber\tease\tinterval\trepetitions\tlapses\tdue\tlast_review";
+
+const INITIAL_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+
+/// The review schedule for one labelled number, using SM-2. Days are counted from the Unix epoch.
+#[derive(Clone, Debug)]
+pub struct ReviewItem {
+    pub label: String,
+    // Digits only.
+    pub number: String,
+    pub ease: f64,
+    // Days until the next review after the last successful one.
+    pub interval: u32,
+    // Successful reviews in a row. A lapse sets this back to zero.
+    pub repetitions: u32,
+    pub lapses: u32,
+    pub due: i64,
+    pub last_review: Option<i64>,
+}
+
+/// Which way a review goes. Both check the answer by turning it into digits, so any phrase that
+/// encodes the number is right, not just the one the search proposed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// Show the label and ask for the number, typed as digits or as a phrase.
+    LabelToNumber,
+    /// Show the number and ask for a phrase that encodes it.
+    NumberToPhrase,
+}
+
+/// How well a review went, from the SM-2 scale of 0 to 5. Anything below Hard is a lapse.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Grade {
+    Wrong,
+    Hard,
+    Good,
+    Easy,
+}
+
+#[derive(Clone, Debug)]
+pub struct AnswerCheck {
+    pub correct: bool,
+    // The digits the answer works out to, if it could be read at all.
+    pub digits: Option<String>,
+    // Why the answer couldn't be read, such as words missing from the dictionary.
+    pub message: Option<String>,
+}
+
+/// Every labelled number being practiced, from one or more numbers files.
+#[derive(Clone, Debug, Default)]
+pub struct PracticeState {
+    pub items: Vec<ReviewItem>,
+}
+
+impl ReviewItem {
+    pub fn new(label: &str, number: &str, today: i64) -> Self {
+        Self {
+            label: label.to_string(),
+            number: parse::digits_only(number),
+            ease: INITIAL_EASE,
+            interval: 0,
+            repetitions: 0,
+            lapses: 0,
+            due: today,
+            last_review: None,
+        }
+    }
+
+    pub fn is_due(&self, today: i64) -> bool {
+        self.due <= today
+    }
+
+    /// Alternates between the two directions as the item is reviewed, starting with the label.
+    pub fn direction(&self) -> Direction {
+        if (self.repetitions + self.lapses).is_multiple_of(2) {
+            Direction::LabelToNumber
+        } else {
+            Direction::NumberToPhrase
+        }
+    }
+
+    /// Updates the schedule with the standard SM-2 rules.
+    pub fn review(&mut self, grade: Grade, today: i64) {
+        let quality = grade.quality();
+        if quality < 3 {
+            self.repetitions = 0;
+            self.interval = 1;
+            self.lapses += 1;
+        } else {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as f64 * self.ease).round() as u32,
+            };
+            self.repetitions += 1;
+        }
+        let miss = (5 - quality) as f64;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+        self.due = today + self.interval as i64;
+        self.last_review = Some(today);
+    }
+}
+
+impl Grade {
+    pub fn quality(&self) -> u8 {
+        match self {
+            Grade::Wrong => 1,
+            Grade::Hard => 3,
+            Grade::Good => 4,
+            Grade::Easy => 5,
+        }
+    }
+}
+
+impl PracticeState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a state file written by save(). A file that doesn't exist yet is an empty state.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        match File::open(path.as_ref()) {
+            Ok(file) => Self::from_reader(BufReader::new(file), &file_name),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(LoadError::io(&file_name, e)),
+        }
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R, source_name: &str) -> Result<Self, LoadError> {
+        let mut state = Self::new();
+        for (index, line) in reader.lines().enumerate().skip(1) {
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_number = index + 1;
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let field = |field_index: usize, field_name: &str| {
+                fields.get(field_index).copied().ok_or_else(|| LoadError::missing_field(source_name, line_number, field_name))
+            };
+            let last_review = field(7, "last_review")?;
+            state.items.push(ReviewItem {
+                label: field(0, "label")?.to_string(),
+                number: field(1, "number")?.to_string(),
+                ease: words::parse_field(field(2, "ease")?, source_name, line_number, "ease")?,
+                interval: words::parse_field(field(3, "interval")?, source_name, line_number, "interval")?,
+                repetitions: words::parse_field(field(4, "repetitions")?, source_name, line_number, "repetitions")?,
+                lapses: words::parse_field(field(5, "lapses")?, source_name, line_number, "lapses")?,
+                due: words::parse_field(field(6, "due")?, source_name, line_number, "due")?,
+                last_review: if last_review.is_empty() {
+                    None
+                } else {
+                    Some(words::parse_field(last_review, source_name, line_number, "last_review")?)
+                },
+            });
+        }
+        Ok(state)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let mut text = format!("{}\n", STATE_HEADER);
+        for item in self.items.iter() {
+            text.push_str(&format!("{}\t{}\t{:.3}\t{}\t{}\t{}\t{}\t{}\n",
+                item.label.replace('\t', " "),
+                item.number,
+                item.ease,
+                item.interval,
+                item.repetitions,
+                item.lapses,
+                item.due,
+                item.last_review.map_or("".to_string(), |day| day.to_string())));
+        }
+        fs::write(&path, text).map_err(|e| format!("Unable to write \"{}\": {}", path.as_ref().display(), e))
+    }
+
+    /// Adds the (label, number) pairs from a numbers file like load_numbers() returns and gives
+    /// back the indexes of their items, in the order of the file. Items that are already scheduled
+    /// keep their history and new numbers are due today. A label whose number has changed starts
+    /// over. Items for numbers that aren't in this file are kept, so that one state file can track
+    /// several numbers files.
+    pub fn sync(&mut self, numbers: &[(String, String)], today: i64) -> Vec<usize> {
+        let mut indexes = vec![];
+        for (label, number) in numbers.iter() {
+            let number = parse::digits_only(number);
+            let index = match self.items.iter().position(|item| item.label == *label) {
+                Some(index) if self.items[index].number == number => index,
+                Some(index) => {
+                    self.items[index] = ReviewItem::new(label, &number, today);
+                    index
+                },
+                None => {
+                    self.items.push(ReviewItem::new(label, &number, today));
+                    self.items.len() - 1
+                },
+            };
+            indexes.push(index);
+        }
+        indexes
+    }
+
+    /// Of the given item indexes, the ones due today or earlier, most overdue first.
+    pub fn due_items(&self, indexes: &[usize], today: i64) -> Vec<usize> {
+        let mut due_items = indexes.iter().copied().filter(|&index| self.items[index].is_due(today)).collect::<Vec<_>>();
+        due_items.sort_by_key(|&index| self.items[index].due);
+        due_items
+    }
+}
+
+/// The current day counted from the Unix epoch, in UTC.
+pub fn today() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| (duration.as_secs() / 86_400) as i64)
+}
+
+/// Checks a typed answer for the item. An answer with letters in it is decoded from phrase to
+/// digits with encoding::decode_phrase(), guessing words missing from the pronunciations only if
+/// asked to. A bare number is only accepted when asked for the number itself.
+pub fn check_answer(pronunciations: &PronunciationMap, encoding: &dyn Encoding, item: &ReviewItem, direction: Direction, answer: &str, guess_unknown: bool) -> AnswerCheck {
+    if !answer.chars().any(char::is_alphabetic) {
+        let digits = parse::digits_only(answer);
+        return AnswerCheck {
+            correct: direction == Direction::LabelToNumber && digits == item.number,
+            message: if direction == Direction::NumberToPhrase { Some("Expected a phrase.".to_string()) } else { None },
+            digits: Some(digits),
+        };
+    }
+    match encoding::decode_phrase(pronunciations, encoding, answer, guess_unknown) {
+        Ok(decoding) => AnswerCheck {
+            correct: decoding.number == item.number,
+            digits: Some(decoding.number),
+            message: None,
+        },
+        Err(e) => AnswerCheck {
+            correct: false,
+            digits: None,
+            message: Some(e.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scheme::TableScheme;
+    use crate::words::{Pronunciation, PronunciationMap, Variants};
+    use super::{check_answer, Direction, Grade, PracticeState, ReviewItem, MIN_EASE};
+
+    const PRONUNCIATIONS: &str = "\
+CAT  K AE1 T
+NET  N EH1 T
+";
+
+    fn pronunciations() -> PronunciationMap {
+        Pronunciation::index(Pronunciation::from_reader(PRONUNCIATIONS.as_bytes(), "pronunciations", None, &TableScheme::major(), Variants::All).unwrap())
+    }
+
+    fn numbers(numbers: &[(&str, &str)]) -> Vec<(String, String)> {
+        numbers.iter().map(|(label, number)| (label.to_string(), number.to_string())).collect()
+    }
+
+    fn assert_ease(item: &ReviewItem, ease: f64) {
+        assert!((item.ease - ease).abs() < 1e-9, "ease {} instead of {}", item.ease, ease);
+    }
+
+    #[test]
+    fn review_follows_sm2_intervals() {
+        let mut item = ReviewItem::new("Pin", "71-21", 100);
+        assert_eq!(item.number, "7121");
+        assert!(item.is_due(100));
+        item.review(Grade::Good, 100);
+        assert_eq!((item.interval, item.repetitions, item.due), (1, 1, 101));
+        assert_ease(&item, 2.5);
+        item.review(Grade::Good, 101);
+        assert_eq!((item.interval, item.repetitions, item.due), (6, 2, 107));
+        item.review(Grade::Easy, 107);
+        assert_eq!((item.interval, item.repetitions, item.due), (15, 3, 122));
+        assert_ease(&item, 2.6);
+        assert!(!item.is_due(121));
+        item.review(Grade::Hard, 122);
+        assert_eq!((item.interval, item.repetitions, item.lapses), (39, 4, 0));
+        assert_ease(&item, 2.46);
+        assert_eq!(item.last_review, Some(122));
+    }
+
+    #[test]
+    fn a_wrong_answer_starts_the_repetitions_over() {
+        let mut item = ReviewItem::new("Pin", "7121", 100);
+        item.review(Grade::Good, 100);
+        item.review(Grade::Good, 101);
+        item.review(Grade::Wrong, 107);
+        assert_eq!((item.interval, item.repetitions, item.lapses, item.due), (1, 0, 1, 108));
+        assert_ease(&item, 1.96);
+        item.review(Grade::Good, 108);
+        assert_eq!((item.interval, item.repetitions), (1, 1));
+    }
+
+    #[test]
+    fn ease_stops_at_the_floor() {
+        let mut item = ReviewItem::new("Pin", "7121", 100);
+        for day in 100..105 {
+            item.review(Grade::Wrong, day);
+        }
+        assert_ease(&item, MIN_EASE);
+        assert_eq!(item.lapses, 5);
+    }
+
+    #[test]
+    fn direction_alternates_with_each_passed_review() {
+        let mut item = ReviewItem::new("Pin", "7121", 100);
+        assert_eq!(item.direction(), Direction::LabelToNumber);
+        item.review(Grade::Good, 100);
+        assert_eq!(item.direction(), Direction::NumberToPhrase);
+        item.review(Grade::Good, 101);
+        assert_eq!(item.direction(), Direction::LabelToNumber);
+        item.review(Grade::Hard, 107);
+        assert_eq!(item.direction(), Direction::NumberToPhrase);
+    }
+
+    #[test]
+    fn sync_adds_keeps_and_restarts_items() {
+        let mut state = PracticeState::new();
+        assert_eq!(state.sync(&numbers(&[("Pin", "7121"), ("Door", "3141")]), 100), vec![0, 1]);
+        state.items[0].review(Grade::Good, 100);
+        state.items[1].review(Grade::Good, 100);
+        // Another numbers file with one label in common.
+        assert_eq!(state.sync(&numbers(&[("Phone", "555"), ("Pin", "7121")]), 101), vec![2, 0]);
+        assert_eq!(state.items[0].repetitions, 1);
+        assert_eq!(state.items[1].label, "Door");
+        // A changed number starts over.
+        assert_eq!(state.sync(&numbers(&[("Door", "2718")]), 102), vec![1]);
+        assert_eq!((state.items[1].number.as_str(), state.items[1].repetitions, state.items[1].due), ("2718", 0, 102));
+        assert_eq!(state.items.len(), 3);
+    }
+
+    #[test]
+    fn due_items_are_most_overdue_first() {
+        let mut state = PracticeState::new();
+        let indexes = state.sync(&numbers(&[("a", "1"), ("b", "2"), ("c", "3")]), 100);
+        state.items[0].due = 103;
+        state.items[1].due = 99;
+        state.items[2].due = 101;
+        assert_eq!(state.due_items(&indexes, 102), vec![1, 2]);
+        assert_eq!(state.due_items(&indexes[..1], 102), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn state_reads_back_what_it_saves() {
+        let text = "label\tnumber\tease\tinterval\trepetitions\tlapses\tdue\tlast_review\nPin\t7121\t2.360\t6\t2\t1\t107\t101\nDoor\t3141\t2.500\t0\t0\t0\t100\t\n";
+        let state = PracticeState::from_reader(text.as_bytes(), "state").unwrap();
+        assert_eq!(state.items.len(), 2);
+        assert_eq!((state.items[0].interval, state.items[0].repetitions, state.items[0].lapses, state.items[0].due, state.items[0].last_review), (6, 2, 1, 107, Some(101)));
+        assert_eq!(state.items[1].last_review, None);
+        assert!(PracticeState::from_reader("header\nPin\t7121\t2.5\n".as_bytes(), "state").is_err());
+        assert!(PracticeState::from_reader("header\nPin\t7121\tx\t0\t0\t0\t100\t\n".as_bytes(), "state").is_err());
+    }
+
+    #[test]
+    fn check_answer_takes_digits_or_a_phrase() {
+        let pronunciations = pronunciations();
+        let scheme = TableScheme::major();
+        let item = ReviewItem::new("Pin", "7121", 100);
+        let check = |direction, answer: &str, guess_unknown| check_answer(&pronunciations, &scheme, &item, direction, answer, guess_unknown);
+        assert!(check(Direction::LabelToNumber, "71 21", false).correct);
+        assert!(!check(Direction::LabelToNumber, "7122", false).correct);
+        let digits_for_a_phrase = check(Direction::NumberToPhrase, "7121", false);
+        assert!(!digits_for_a_phrase.correct);
+        assert_eq!(digits_for_a_phrase.message.as_deref(), Some("Expected a phrase."));
+        assert!(check(Direction::NumberToPhrase, "Cat, net", false).correct);
+        assert!(check(Direction::LabelToNumber, "cat net", false).correct);
+        let wrong = check(Direction::NumberToPhrase, "net cat", false);
+        assert!(!wrong.correct);
+        assert_eq!(wrong.digits.as_deref(), Some("2171"));
+        let unknown = check(Direction::NumberToPhrase, "cat nut", false);
+        assert!(!unknown.correct && unknown.digits.is_none() && unknown.message.is_some());
+        assert!(check(Direction::NumberToPhrase, "cat nut", true).correct);
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+use crate::mnemonic::Mnemonic;
+
+/// Everything found for one number, for callers to render or inspect.
+#[derive(Clone, Debug)]
+pub struct MnemonicReport {
+    pub label: String,
+    // The number with everything but the digits removed.
+    pub number: String,
+    /// The segmentations of the number into the fewest words.
+    pub paths: Vec<PathCandidate>,
+    /// The best phrases across all of the paths, best first.
+    pub best: Vec<Mnemonic>,
+    pub elapsed: Duration,
+}
+
+/// One way of splitting the number into codes, with the words that fit each code.
+#[derive(Clone, Debug)]
+pub struct PathCandidate {
+    pub segments: Vec<String>,
+    /// One list of words per segment.
+    pub choices: Vec<Vec<WordChoice>>,
+    /// Longer words whose codes start with the last segment. These work when the extra digits can
+    /// be ignored, as with a PIN or phone number where the length is known in advance.
+    pub extra: Vec<WordChoice>,
+}
+
+#[derive(Clone, Debug)]
+pub struct WordChoice {
+    pub word: String,
+    pub code: String,
+    pub rank: usize,
+    pub frequency: usize,
+}
+
+impl PathCandidate {
+    pub fn segmentation(&self) -> String {
+        self.segments.join("-")
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const SCHEME_NAME_MAJOR: &str = "major";
+pub const SCHEME_NAME_MAJOR_TH: &str = "major-th";
+pub const SCHEME_NAME_MAJOR_GERMAN: &str = "major-de";
+pub const SCHEME_NAME_MAJOR_SPANISH: &str = "major-es";
+pub const SCHEME_NAME_MAJOR_FRENCH: &str = "major-fr";
+pub const SCHEME_NAME_MAJOR_IPA: &str = "major-ipa";
+
+// The classic Major System over ARPAbet phones. Vowels and weak sounds like "HH", "W" and "Y"
+// don't contribute a digit.
+const MAJOR_TABLE: [(&str, &str); 39] = [
+    ("AA", ""), ("AE", ""), ("AH", ""), ("AO", ""), ("AW", ""), ("AY", ""),
+    ("B", "9"), ("CH", "6"), ("D", "1"), ("DH", ""), ("EH", ""), ("ER", "4"),
+    ("EY", ""), ("F", "8"), ("G", "7"), ("HH", ""), ("IH", ""), ("IY", ""),
+    ("JH", "6"), ("K", "7"), ("L", "5"), ("M", "3"), ("N", "2"), ("NG", "2"),
+    ("OW", ""), ("OY", ""), ("P", "9"), ("R", "4"), ("S", "0"), ("SH", "6"),
+    ("T", "1"), ("TH", "1"), ("UH", ""), ("UW", ""), ("V", "8"), ("W", ""),
+    ("Y", ""), ("Z", "0"), ("ZH", "6"),
+];
+
+// A language-neutral Major System over IPA phones, for any lexicon tokenized by ipa::tokenize().
+// Sounds are counted as they are in English, so "ts" is T + S and "pf" is P + F.
+const MAJOR_IPA_TABLE: [(&str, &str); 88] = [
+    ("s", "0"), ("z", "0"), ("ts", "10"), ("dz", "10"), ("θ", "1"), ("ð", ""), ("t", "1"),
+    ("d", "1"), ("ɾ", "1"), ("n", "2"), ("ŋ", "2"), ("ɲ", "2"), ("m", "3"), ("ɱ", "3"), ("ɹ", "4"),
+    ("r", "4"), ("ʁ", "4"), ("ʀ", "4"), ("ɻ", "4"), ("ɚ", "4"), ("ɝ", "4"), ("l", "5"), ("ɫ", "5"),
+    ("ʎ", "5"), ("ʃ", "6"), ("ʒ", "6"), ("tʃ", "6"), ("dʒ", "6"), ("ç", "6"), ("ɕ", "6"),
+    ("ʑ", "6"), ("tɕ", "6"), ("dʑ", "6"), ("k", "7"), ("g", "7"), ("ɡ", "7"), ("x", "7"),
+    ("ɣ", "7"), ("q", "7"), ("χ", "7"), ("f", "8"), ("v", "8"), ("ʋ", "8"), ("pf", "98"),
+    ("p", "9"), ("b", "9"), ("β", "9"), ("h", ""), ("ɦ", ""), ("ʔ", ""), ("j", ""), ("w", ""),
+    ("ɥ", ""), ("ʍ", ""), ("a", ""), ("e", ""), ("i", ""), ("o", ""), ("u", ""), ("y", ""),
+    ("æ", ""), ("ɑ", ""), ("ɐ", ""), ("ɒ", ""), ("ɛ", ""), ("ɜ", ""), ("ə", ""), ("ɘ", ""),
+    ("ɪ", ""), ("ɨ", ""), ("ʉ", ""), ("ʊ", ""), ("ʌ", ""), ("ɔ", ""), ("ø", ""), ("œ", ""),
+    ("ɤ", ""), ("ɯ", ""), ("ɵ", ""), ("ɶ", ""), ("ʏ", ""), ("aɪ", ""), ("aʊ", ""), ("eɪ", ""),
+    ("oʊ", ""), ("ɔɪ", ""), ("ɔʏ", ""), ("əʊ", ""),
+];
+
+// The Major System for other languages, over IPA phones written one per token like "ʃ t ʁ aː s ə".
+// Each lists its vowels and glides as well so that a phone missing from the table is an error
+// rather than silently ignored.
+const MAJOR_GERMAN_TABLE: [(&str, &str); 57] = [
+    ("s", "0"), ("z", "0"), ("ts", "0"), ("t", "1"), ("d", "1"), ("n", "2"), ("ŋ", "2"),
+    ("m", "3"), ("ʁ", "4"), ("r", "4"), ("ʀ", "4"), ("l", "5"), ("ʃ", "6"), ("ʒ", "6"),
+    ("tʃ", "6"), ("dʒ", "6"), ("ç", "6"), ("j", "6"), ("k", "7"), ("g", "7"), ("ɡ", "7"),
+    ("x", "7"), ("f", "8"), ("v", "8"), ("pf", "8"), ("p", "9"), ("b", "9"), ("h", ""),
+    ("ʔ", ""), ("a", ""), ("aː", ""), ("ɛ", ""), ("ɛː", ""), ("e", ""), ("eː", ""), ("ə", ""),
+    ("ɐ", ""), ("ɪ", ""), ("i", ""), ("iː", ""), ("ɔ", ""), ("o", ""), ("oː", ""), ("œ", ""),
+    ("ø", ""), ("øː", ""), ("ʊ", ""), ("u", ""), ("uː", ""), ("ʏ", ""), ("y", ""), ("yː", ""),
+    ("aɪ", ""), ("aʊ", ""), ("ɔʏ", ""), ("ɐ̯", ""), ("w", ""),
+];
+
+const MAJOR_SPANISH_TABLE: [(&str, &str); 38] = [
+    ("s", "0"), ("z", "0"), ("θ", "0"), ("t", "1"), ("d", "1"), ("ð", "1"), ("n", "2"),
+    ("ɲ", "2"), ("ŋ", "2"), ("m", "3"), ("r", "4"), ("ɾ", "4"), ("l", "5"), ("ʎ", "5"),
+    ("tʃ", "6"), ("ʝ", "6"), ("ʃ", "6"), ("k", "7"), ("g", "7"), ("ɡ", "7"), ("ɣ", "7"),
+    ("x", "7"), ("f", "8"), ("p", "9"), ("b", "9"), ("β", "9"), ("a", ""), ("e", ""), ("i", ""),
+    ("o", ""), ("u", ""), ("j", ""), ("w", ""), ("i̯", ""), ("u̯", ""), ("h", ""), ("ʔ", ""),
+    ("ɟʝ", "6"),
+];
+
+const MAJOR_FRENCH_TABLE: [(&str, &str); 44] = [
+    ("s", "0"), ("z", "0"), ("t", "1"), ("d", "1"), ("n", "2"), ("ɲ", "2"), ("ŋ", "2"),
+    ("m", "3"), ("ʁ", "4"), ("r", "4"), ("l", "5"), ("ʃ", "6"), ("ʒ", "6"), ("tʃ", "6"),
+    ("dʒ", "6"), ("k", "7"), ("g", "7"), ("ɡ", "7"), ("f", "8"), ("v", "8"), ("p", "9"),
+    ("b", "9"), ("j", ""), ("w", ""), ("ɥ", ""), ("a", ""), ("ɑ", ""), ("e", ""), ("ɛ", ""),
+    ("i", ""), ("o", ""), ("ɔ", ""), ("u", ""), ("y", ""), ("ø", ""), ("œ", ""), ("ə", ""),
+    ("ɑ̃", ""), ("ɛ̃", ""), ("ɔ̃", ""), ("œ̃", ""), ("h", ""), ("ʔ", ""), ("x", "7"),
+];
+
+/// Maps the phones of a pronunciation to the digits they stand for.
+pub trait PhoneticScheme {
+    fn name(&self) -> &str;
+
+    /// The digits for a single phone, or an empty string if the phone is ignored.
+    fn phone_to_digits(&self, phone: &str) -> Result<String, String>;
+}
+
+/// A phonetic scheme driven by a lookup table from phone to digits.
+#[derive(Clone, Debug)]
+pub struct TableScheme {
+    name: String,
+    table: BTreeMap<String, String>,
+}
+
+impl TableScheme {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            table: BTreeMap::new(),
+        }
+    }
+
+    pub fn major() -> Self {
+        let mut scheme = Self::new(SCHEME_NAME_MAJOR);
+        for (phone, digits) in MAJOR_TABLE.iter() {
+            scheme.set(phone, digits);
+        }
+        scheme
+    }
+
+    /// A common variant where both "th" sounds count as 1 and "ng" is heard as N + G.
+    pub fn major_th() -> Self {
+        let mut scheme = Self::major();
+        scheme.name = SCHEME_NAME_MAJOR_TH.to_string();
+        scheme.set("DH", "1");
+        scheme.set("NG", "27");
+        scheme
+    }
+
+    /// The Major System over the IPA phones of a German, Spanish or French lexicon, or of any IPA
+    /// lexicon with major_ipa().
+    pub fn major_german() -> Self {
+        Self::from_table(SCHEME_NAME_MAJOR_GERMAN, &MAJOR_GERMAN_TABLE)
+    }
+
+    pub fn major_spanish() -> Self {
+        Self::from_table(SCHEME_NAME_MAJOR_SPANISH, &MAJOR_SPANISH_TABLE)
+    }
+
+    pub fn major_french() -> Self {
+        Self::from_table(SCHEME_NAME_MAJOR_FRENCH, &MAJOR_FRENCH_TABLE)
+    }
+
+    pub fn major_ipa() -> Self {
+        Self::from_table(SCHEME_NAME_MAJOR_IPA, &MAJOR_IPA_TABLE)
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            SCHEME_NAME_MAJOR => Some(Self::major()),
+            SCHEME_NAME_MAJOR_TH => Some(Self::major_th()),
+            SCHEME_NAME_MAJOR_GERMAN => Some(Self::major_german()),
+            SCHEME_NAME_MAJOR_SPANISH => Some(Self::major_spanish()),
+            SCHEME_NAME_MAJOR_FRENCH => Some(Self::major_french()),
+            SCHEME_NAME_MAJOR_IPA => Some(Self::major_ipa()),
+            _ => None,
+        }
+    }
+
+    /// Either a built-in scheme by name or a scheme file.
+    pub fn from_name_or_path(name_or_path: &str) -> Result<Self, String> {
+        match Self::built_in(name_or_path) {
+            Some(scheme) => Ok(scheme),
+            None => Self::from_path(name_or_path),
+        }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read scheme file {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|message| format!("{} in {}", message, path.display()))
+    }
+
+    /// Parses a small TOML-style scheme file like:
+    ///
+    /// ```text
+    /// name = "team"
+    /// base = "major"
+    ///
+    /// [phones]
+    /// DH = "1"
+    /// NG = "27"
+    /// ```
+    ///
+    /// Phones not listed come from the base scheme, which defaults to "major". Use "" for a phone
+    /// that shouldn't contribute a digit. The base can also be one of the IPA schemes like
+    /// "major-de", whose phones are kept as written rather than uppercased like ARPAbet.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut base = SCHEME_NAME_MAJOR.to_string();
+        let mut entries = vec![];
+        let mut in_phones = false;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_phones = match line {
+                    "[phones]" => true,
+                    _ => return Err(format!("Unexpected section {} on line {}", line, index + 1)),
+                };
+                continue;
+            }
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("Expected key = value on line {}", index + 1))?;
+            let key = key.trim();
+            let value = value.trim().trim_matches('"').to_string();
+            if in_phones {
+                if !value.chars().all(|c| c.is_ascii_digit()) {
+                    return Err(format!("Expected only digits for {} on line {}", key, index + 1));
+                }
+                entries.push((key.to_string(), value));
+            } else {
+                match key {
+                    "name" => name = Some(value),
+                    "base" => base = value,
+                    _ => return Err(format!("Unexpected key {} on line {}", key, index + 1)),
+                }
+            }
+        }
+        let mut scheme = Self::built_in(&base).ok_or_else(|| format!("Unknown base scheme {}", base))?;
+        scheme.name = name.unwrap_or_else(|| format!("{} (custom)", base));
+        let arpabet = base == SCHEME_NAME_MAJOR || base == SCHEME_NAME_MAJOR_TH;
+        for (phone, digits) in entries {
+            let phone = if arpabet { phone.to_uppercase() } else { phone };
+            scheme.set(&phone, &digits);
+        }
+        Ok(scheme)
+    }
+
+    pub fn set(&mut self, phone: &str, digits: &str) {
+        self.table.insert(phone.to_string(), digits.to_string());
+    }
+
+    fn from_table(name: &str, table: &[(&str, &str)]) -> Self {
+        let mut scheme = Self::new(name);
+        for (phone, digits) in table.iter() {
+            scheme.set(phone, digits);
+        }
+        scheme
+    }
+}
+
+impl Default for TableScheme {
+    fn default() -> Self {
+        Self::major()
+    }
+}
+
+impl PhoneticScheme for TableScheme {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn phone_to_digits(&self, phone: &str) -> Result<String, String> {
+        // Vowels carry a stress digit like "AH0" or "ER1".
+        let phone = phone.trim_end_matches(|c: char| c.is_ascii_digit());
+        match self.table.get(phone) {
+            Some(digits) => Ok(digits.clone()),
+            None => Err(format!("Unexpected phone = {}", phone)),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+use crate::words::Word;
+
+/// Scores candidate words for a mnemonic. As with the original sum of ranks, lower scores are
+/// better. A phrase's score is the sum of its words' scores so that searches can build phrases up
+/// one word at a time.
+pub trait Scorer {
+    fn score_word(&self, word: &Word) -> ScoreBreakdown;
+
+    fn score(&self, words: &[&Word]) -> ScoreBreakdown {
+        let mut breakdown = ScoreBreakdown::default();
+        for word in words.iter() {
+            breakdown.add(&self.score_word(word));
+        }
+        breakdown
+    }
+}
+
+/// The parts that make up a score so that callers can see why one phrase beat another.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    pub word_count: f64,
+    pub frequency: f64,
+    pub dispersion: f64,
+    pub part_of_speech: f64,
+    pub concreteness: f64,
+    // The search's penalty for a word that breaks the rhythm of the split. Scorers leave it at 0.
+    pub segmentation: f64,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> f64 {
+        self.word_count + self.frequency + self.dispersion + self.part_of_speech + self.concreteness + self.segmentation
+    }
+
+    pub fn add(&mut self, other: &ScoreBreakdown) {
+        self.word_count += other.word_count;
+        self.frequency += other.frequency;
+        self.dispersion += other.dispersion;
+        self.part_of_speech += other.part_of_speech;
+        self.concreteness += other.concreteness;
+        self.segmentation += other.segmentation;
+    }
+}
+
+/// A weighted combination of the word list's columns:
+///   - a fixed cost per word so that shorter phrases win,
+///   - the log of the frequency rank so that common words win but a rank of 4,000 isn't a
+///     hundred times worse than a rank of 40,
+///   - one minus the dispersion so that words used across many kinds of text beat jargon, and
+///   - a cost per part of speech since nouns and verbs are easier to picture than articles or
+///     prepositions, and
+///   - one minus the concreteness so that "cat" beats "however". This is off unless
+///     concreteness_weight is set, since most word lists have no norms loaded.
+#[derive(Clone, Debug)]
+pub struct WeightedScorer {
+    pub word_weight: f64,
+    pub rank_weight: f64,
+    pub dispersion_weight: f64,
+    // Keyed by the part of speech codes in the word list, like "n" for noun.
+    pub part_of_speech_costs: BTreeMap<String, f64>,
+    pub other_part_of_speech_cost: f64,
+    pub concreteness_weight: f64,
+    // The concreteness to assume for words that weren't rated.
+    pub unknown_concreteness: f64,
+}
+
+impl WeightedScorer {
+    pub fn new() -> Self {
+        let mut part_of_speech_costs = BTreeMap::new();
+        for (part_of_speech, cost) in [("n", 0.0), ("v", 0.5), ("j", 0.5), ("m", 1.0), ("r", 1.5)].iter() {
+            part_of_speech_costs.insert(part_of_speech.to_string(), *cost);
+        }
+        Self {
+            word_weight: 10.0,
+            rank_weight: 1.0,
+            dispersion_weight: 2.0,
+            part_of_speech_costs,
+            other_part_of_speech_cost: 3.0,
+            concreteness_weight: 0.0,
+            unknown_concreteness: 0.5,
+        }
+    }
+}
+
+impl Default for WeightedScorer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scorer for WeightedScorer {
+    fn score_word(&self, word: &Word) -> ScoreBreakdown {
+        ScoreBreakdown {
+            word_count: self.word_weight,
+            frequency: self.rank_weight * (word.rank.max(1) as f64).ln(),
+            dispersion: self.dispersion_weight * (1.0 - word.dispersion).max(0.0),
+            part_of_speech: *self.part_of_speech_costs.get(&word.part_of_speech).unwrap_or(&self.other_part_of_speech_cost),
+            concreteness: self.concreteness_weight * (1.0 - word.concreteness.unwrap_or(self.unknown_concreteness)).max(0.0),
+            segmentation: 0.0,
+        }
+    }
+}
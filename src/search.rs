@@ -0,0 +1,564 @@
+use ordered_float::NotNan;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+use std::time::Instant;
+use crate::filter::ContentFilter;
+use crate::mnemonic::{Mnemonic, WordsBTreeMap};
+use crate::report::{MnemonicReport, PathCandidate, WordChoice};
+use crate::score::{ScoreBreakdown, Scorer};
+use crate::segmentation::SegmentationPolicy;
+use crate::template::PosTemplate;
+use crate::words::WordList;
+use crate::itertools::Itertools;
+use crate::util::parse;
+
+/// A search over a digit-prefix trie of the word index. Rather than trying every composition of
+/// the number like gen_paths(), it finds every code that starts at each position in one walk of
+/// the trie, works out the cheapest way to finish from each position, and then pulls complete
+/// phrases off a priority queue in score order so that only the best few are ever built.
+pub struct SearchEngine {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    pub max_words: usize,
+    pub top_n: usize,
+    // If true, only use segmentations with the fewest possible words, the same as
+    // propose_mnemonics_path(). Otherwise a phrase with more words can win if its words are better.
+    pub fewest_words: bool,
+    // If set, only phrases with one word per slot whose parts of speech fit the template. The
+    // template sets the number of words, so fewest_words is ignored.
+    pub template: Option<PosTemplate>,
+    // If set, neighbouring words that make a blocked pair aren't used together. Blocked single
+    // words should already be left out of the index.
+    pub content_filter: Option<ContentFilter>,
+    // Which splits of the number are allowed and how much uneven ones cost.
+    pub segmentation: SegmentationPolicy,
+}
+
+#[derive(Default)]
+struct TrieNode {
+    children: [Option<usize>; 10],
+    // The words whose code ends at this node, best first.
+    candidates: Vec<Candidate>,
+}
+
+struct Candidate {
+    word: String,
+    code: String,
+    rank: usize,
+    frequency: usize,
+    part_of_speech: String,
+    breakdown: ScoreBreakdown,
+    cost: NotNan<f64>,
+}
+
+// One step of a partial phrase. Steps are kept in an arena and point back to the step before so
+// that partial phrases on the queue share their common beginnings.
+struct Step {
+    parent: Option<usize>,
+    position: usize,
+    word_count: usize,
+    cost: NotNan<f64>,
+    // The trie node and candidate index of the word for this step, if this isn't the root.
+    word: Option<(usize, usize)>,
+}
+
+impl Candidate {
+    fn word_choice(&self) -> WordChoice {
+        WordChoice {
+            word: self.word.clone(),
+            code: self.code.clone(),
+            rank: self.rank,
+            frequency: self.frequency,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn new() -> Self {
+        Self {
+            max_words: usize::MAX,
+            top_n: 5,
+            fewest_words: true,
+            template: None,
+            content_filter: None,
+            segmentation: SegmentationPolicy::new(),
+        }
+    }
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchEngine {
+    /// Builds the trie from an index made by gen_btreemap(), scoring each word once up front.
+    pub fn new(word_list: &WordList, words: &WordsBTreeMap, scorer: &dyn Scorer) -> Self {
+        let mut engine = Self {
+            nodes: vec![TrieNode::default()],
+        };
+        for (code, code_words) in words.iter() {
+            let node_index = match engine.insert(code) {
+                Some(node_index) => node_index,
+                None => continue,
+            };
+            for (word, rank) in code_words.iter() {
+                if let Some(entry) = word_list.words.get(&word.to_lowercase()) {
+                    let breakdown = scorer.score_word(entry);
+                    let cost = NotNan::new(breakdown.total()).unwrap();
+                    engine.nodes[node_index].candidates.push(Candidate {
+                        word: word.clone(),
+                        code: code.clone(),
+                        rank: *rank,
+                        frequency: entry.frequency,
+                        part_of_speech: entry.part_of_speech.clone(),
+                        breakdown,
+                        cost,
+                    });
+                }
+            }
+            engine.nodes[node_index].candidates.sort_by(|a, b| a.cost.cmp(&b.cost).then_with(|| a.word.cmp(&b.word)));
+        }
+        engine
+    }
+
+    pub fn contains_code(&self, code: &str) -> bool {
+        self.find(code).is_some_and(|node_index| !self.nodes[node_index].candidates.is_empty())
+    }
+
+    /// The words for a code, best first.
+    pub fn word_choices(&self, code: &str) -> Vec<WordChoice> {
+        match self.find(code) {
+            Some(node_index) => self.nodes[node_index].candidates.iter().map(Candidate::word_choice).collect(),
+            None => vec![],
+        }
+    }
+
+    /// The words for a code that fit one slot of a template, best first.
+    pub fn fitting_word_choices(&self, code: &str, template: &PosTemplate, slot: usize) -> Vec<WordChoice> {
+        match self.find(code) {
+            Some(node_index) => self.nodes[node_index].candidates.iter()
+                .filter(|candidate| template.fits(slot, &candidate.part_of_speech))
+                .map(Candidate::word_choice)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Words whose codes start with the given code but are longer, like "executive" (70718) for
+    /// 707. These fit when the extra digits can be ignored, such as at the end of a PIN.
+    pub fn extra_words(&self, code: &str) -> Vec<WordChoice> {
+        let mut extra_words = vec![];
+        if let Some(node_index) = self.find(code) {
+            let mut stack = self.nodes[node_index].children.iter().flatten().copied().collect::<Vec<_>>();
+            while let Some(child_index) = stack.pop() {
+                let child = &self.nodes[child_index];
+                extra_words.extend(child.candidates.iter().map(Candidate::word_choice));
+                stack.extend(child.children.iter().flatten().copied());
+            }
+        }
+        extra_words.sort_by(|a, b| a.word.cmp(&b.word));
+        extra_words
+    }
+
+    /// A full report for the number like propose_mnemonics_path() makes, with at most path_limit
+    /// of the fewest-word paths listed.
+    pub fn report(&self, label: &str, number: &str, options: &SearchOptions, path_limit: usize) -> MnemonicReport {
+        let start_time = Instant::now();
+        let best = self.search(number, options);
+        let paths = match &options.template {
+            // The fewest-word paths may not fit the template, so list the ones the best phrases
+            // used, with only the words that fit each slot.
+            Some(template) => best.iter()
+                .map(|mnemonic| mnemonic.codes.clone())
+                .unique()
+                .take(path_limit)
+                .map(|segments| PathCandidate {
+                    choices: segments.iter().enumerate().map(|(slot, segment)| self.fitting_word_choices(segment, template, slot)).collect(),
+                    extra: vec![],
+                    segments,
+                })
+                .collect(),
+            None => self.fewest_words_paths(number, &options.segmentation, path_limit)
+                .into_iter()
+                .map(|segments| PathCandidate {
+                    choices: segments.iter().map(|segment| self.word_choices(segment)).collect(),
+                    extra: self.extra_words(segments.last().unwrap()),
+                    segments,
+                })
+                .collect(),
+        };
+        MnemonicReport {
+            label: label.to_string(),
+            number: parse::digits_only(number),
+            paths,
+            best,
+            elapsed: Instant::now() - start_time,
+        }
+    }
+
+    /// Every segmentation of the number into the fewest words that the policy allows, up to limit
+    /// of them, in the same order as gen_paths() would produce them.
+    pub fn fewest_words_paths(&self, number: &str, segmentation: &SegmentationPolicy, limit: usize) -> Vec<Vec<String>> {
+        let digits = parse::digits_only(number);
+        let edges = self.edges(&digits, segmentation);
+        let min_words = min_words_to_end(&edges);
+        let mut paths = vec![];
+        if min_words[0] != usize::MAX {
+            let mut partial_path = vec![];
+            collect_fewest_words_paths(&digits, &edges, &min_words, 0, &mut partial_path, &mut paths, limit);
+        }
+        paths
+    }
+
+    /// The best phrases for the number, best first.
+    pub fn search(&self, number: &str, options: &SearchOptions) -> Vec<Mnemonic> {
+        let digits = parse::digits_only(number);
+        let edges = self.edges(&digits, &options.segmentation);
+        let min_words = min_words_to_end(&edges);
+        let mut mnemonics = vec![];
+        if digits.is_empty() || min_words[0] == usize::MAX || min_words[0] > options.max_words {
+            return mnemonics;
+        }
+        let template = options.template.as_ref();
+        if let Some(template) = template {
+            if template.len() < min_words[0] || template.len() > options.max_words {
+                return mnemonics;
+            }
+        }
+        let allowed = |from: usize, to: usize| {
+            min_words[to] != usize::MAX && (template.is_some() || !options.fewest_words || min_words[to] + 1 == min_words[from])
+        };
+        let fits = |word_count: usize, candidate: &Candidate| template.is_none_or(|template| template.fits(word_count, &candidate.part_of_speech));
+        let target_width = options.segmentation.target_width(digits.len(), min_words[0]);
+        let penalty = |from: usize, to: usize| NotNan::new(options.segmentation.penalty(from, to, target_width)).unwrap();
+
+        // The cheapest possible cost from each position to the end. Since this is exact when
+        // there's no limit on the number of words, the queue goes almost straight to each answer.
+        // With a template the cost also depends on how many words are already used, so there's
+        // a row for each slot and the last row is only reachable at the end of the number.
+        let rows = template.map_or(1, |template| template.len() + 1);
+        let row = |word_count: usize| if template.is_some() { word_count } else { 0 };
+        let infinity = NotNan::new(f64::INFINITY).unwrap();
+        let mut best_to_end = vec![vec![infinity; digits.len() + 1]; rows];
+        best_to_end[rows - 1][digits.len()] = NotNan::new(0.0).unwrap();
+        for word_count in (0..rows).rev() {
+            if template.is_some() && word_count == rows - 1 {
+                continue;
+            }
+            for from in (0..digits.len()).rev() {
+                for &(to, node_index) in edges[from].iter().filter(|&&(to, _)| allowed(from, to)) {
+                    let best_word = self.nodes[node_index].candidates.iter().find(|candidate| fits(word_count, candidate));
+                    if let Some(best_word) = best_word {
+                        let cost = best_word.cost + penalty(from, to) + best_to_end[row(word_count + 1)][to];
+                        if cost < best_to_end[word_count][from] {
+                            best_to_end[word_count][from] = cost;
+                        }
+                    }
+                }
+            }
+        }
+        if best_to_end[0][0] == infinity {
+            return mnemonics;
+        }
+
+        let mut steps = vec![Step {
+            parent: None,
+            position: 0,
+            word_count: 0,
+            cost: NotNan::new(0.0).unwrap(),
+            word: None,
+        }];
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((best_to_end[0][0], 0)));
+        let mut phrases = BTreeSet::new();
+        while let Some(Reverse((_, step_index))) = queue.pop() {
+            let (position, word_count, cost, previous_word) = {
+                let step = &steps[step_index];
+                let previous_word = step.word.map(|(node_index, candidate_index)| self.nodes[node_index].candidates[candidate_index].word.as_str());
+                (step.position, step.word_count, step.cost, previous_word)
+            };
+            if position == digits.len() {
+                let mnemonic = self.build_mnemonic(&steps, step_index, &penalty);
+                if phrases.insert(mnemonic.phrase.clone()) {
+                    mnemonics.push(mnemonic);
+                    if mnemonics.len() >= options.top_n {
+                        break;
+                    }
+                }
+                continue;
+            }
+            for &(to, node_index) in edges[position].iter().filter(|&&(to, _)| allowed(position, to)) {
+                let best_from_here = best_to_end[row(word_count + 1)][to];
+                if word_count + 1 + min_words[to] > options.max_words || best_from_here == infinity {
+                    continue;
+                }
+                // As in best_mnemonics_for_path(), the best top_n phrases can only use each
+                // segment's best top_n words.
+                let candidates = self.nodes[node_index].candidates.iter()
+                    .enumerate()
+                    .filter(|(_, candidate)| fits(word_count, candidate))
+                    .filter(|(_, candidate)| match (&options.content_filter, previous_word) {
+                        (Some(content_filter), Some(previous_word)) => !content_filter.blocks_pair(previous_word, &candidate.word),
+                        _ => true,
+                    })
+                    .take(options.top_n);
+                let step_penalty = penalty(position, to);
+                for (candidate_index, candidate) in candidates {
+                    let new_cost = cost + candidate.cost + step_penalty;
+                    steps.push(Step {
+                        parent: Some(step_index),
+                        position: to,
+                        word_count: word_count + 1,
+                        cost: new_cost,
+                        word: Some((node_index, candidate_index)),
+                    });
+                    queue.push(Reverse((new_cost + best_from_here, steps.len() - 1)));
+                }
+            }
+        }
+        mnemonics
+    }
+
+    fn insert(&mut self, code: &str) -> Option<usize> {
+        let mut node_index = 0;
+        for digit in code.bytes() {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            let digit = (digit - b'0') as usize;
+            node_index = match self.nodes[node_index].children[digit] {
+                Some(child_index) => child_index,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child_index = self.nodes.len() - 1;
+                    self.nodes[node_index].children[digit] = Some(child_index);
+                    child_index
+                },
+            };
+        }
+        Some(node_index)
+    }
+
+    fn find(&self, code: &str) -> Option<usize> {
+        let mut node_index = 0;
+        for digit in code.bytes() {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            node_index = self.nodes[node_index].children[(digit - b'0') as usize]?;
+        }
+        Some(node_index)
+    }
+
+    // For each starting position, the end positions and trie nodes of every code that has at
+    // least one word, matches the digits from there and makes a split the policy allows. This is
+    // the only place the trie is walked.
+    fn edges(&self, digits: &str, segmentation: &SegmentationPolicy) -> Vec<Vec<(usize, usize)>> {
+        let digits = digits.as_bytes();
+        let mut edges = vec![vec![]; digits.len()];
+        for (from, from_edges) in edges.iter_mut().enumerate() {
+            let mut node_index = 0;
+            for (offset, digit) in digits[from..].iter().enumerate() {
+                match self.nodes[node_index].children[(digit - b'0') as usize] {
+                    Some(child_index) => node_index = child_index,
+                    None => break,
+                }
+                if !self.nodes[node_index].candidates.is_empty() && segmentation.allows(from, from + offset + 1, digits.len()) {
+                    from_edges.push((from + offset + 1, node_index));
+                }
+            }
+        }
+        edges
+    }
+
+    // Penalty gives the segmentation cost of a word from one position to another.
+    fn build_mnemonic(&self, steps: &[Step], last_step_index: usize, penalty: &dyn Fn(usize, usize) -> NotNan<f64>) -> Mnemonic {
+        let mut words = vec![];
+        let mut step_index = Some(last_step_index);
+        while let Some(index) = step_index {
+            if let (Some((node_index, candidate_index)), Some(parent)) = (steps[index].word, steps[index].parent) {
+                let candidate = &self.nodes[node_index].candidates[candidate_index];
+                let mut breakdown = candidate.breakdown.clone();
+                breakdown.segmentation = penalty(steps[parent].position, steps[index].position).into_inner();
+                words.push((candidate, breakdown));
+            }
+            step_index = steps[index].parent;
+        }
+        words
+            .iter()
+            .rev()
+            .fold(Mnemonic::new(), |mnemonic, (candidate, breakdown)| mnemonic.with_word(&candidate.word, &candidate.code, breakdown))
+    }
+}
+
+// The fewest words needed to cover the digits from each position to the end, or usize::MAX if
+// there's no way to do it.
+fn min_words_to_end(edges: &[Vec<(usize, usize)>]) -> Vec<usize> {
+    let mut min_words = vec![usize::MAX; edges.len() + 1];
+    min_words[edges.len()] = 0;
+    for from in (0..edges.len()).rev() {
+        for &(to, _) in edges[from].iter() {
+            if min_words[to] != usize::MAX {
+                min_words[from] = min_words[from].min(min_words[to] + 1);
+            }
+        }
+    }
+    min_words
+}
+
+fn collect_fewest_words_paths(digits: &str, edges: &[Vec<(usize, usize)>], min_words: &[usize], from: usize, partial_path: &mut Vec<String>, paths: &mut Vec<Vec<String>>, limit: usize) {
+    if paths.len() >= limit {
+        return;
+    }
+    if from == digits.len() {
+        paths.push(partial_path.clone());
+        return;
+    }
+    // Longest segment first, like gen_path_internal().
+    for &(to, _) in edges[from].iter().rev() {
+        if min_words[to] != usize::MAX && min_words[to] + 1 == min_words[from] {
+            partial_path.push(digits[from..to].to_string());
+            collect_fewest_words_paths(digits, edges, min_words, to, partial_path, paths, limit);
+            partial_path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::filter::{ContentFilter, FilterCategory, Strictness};
+    use crate::mnemonic::{self, MnemonicOptions, WordsBTreeMap};
+    use crate::scheme::TableScheme;
+    use crate::score::WeightedScorer;
+    use crate::segmentation::SegmentationPolicy;
+    use crate::words::{Pronunciation, Variants, WordList};
+    use super::{SearchEngine, SearchOptions};
+
+    const WORDS: &str = "\
+Rank\tWord\tPart of speech\tFrequency\tDispersion
+1\tthe\ta\t1000\t0.99
+2\tmoney\tn\t900\t0.95
+3\tnet\tn\t800\t0.90
+4\tcat\tn\t700\t0.95
+5\tdog\tn\t650\t0.94
+6\ttie\tv\t600\t0.92
+7\tnoah\tn\t550\t0.80
+8\tcoat\tn\t500\t0.93
+9\tkite\tn\t450\t0.90
+10\ttank\tn\t400\t0.91
+11\tnut\tn\t350\t0.89
+12\ttoad\tn\t300\t0.85
+13\tdot\tn\t250\t0.90
+14\tshoe\tn\t200\t0.93
+15\tcow\tn\t190\t0.92
+16\tbee\tn\t180\t0.91
+17\tsea\tn\t170\t0.92
+18\tlaw\tn\t160\t0.90
+19\tray\tn\t150\t0.88
+20\tivy\tn\t140\t0.70
+21\tma\tn\t130\t0.75
+22\toften\tr\t120\t0.93
+";
+
+    const PRONUNCIATIONS: &str = "\
+THE  DH AH0
+MONEY  M AH1 N IY0
+NET  N EH1 T
+CAT  K AE1 T
+DOG  D AO1 G
+TIE  T AY1
+NOAH  N OW1 AH0
+COAT  K OW1 T
+KITE  K AY1 T
+TANK  T AE1 NG K
+NUT  N AH1 T
+TOAD  T OW1 D
+DOT  D AA1 T
+SHOE  SH UW1
+COW  K AW1
+BEE  B IY1
+SEA  S IY1
+LAW  L AO1
+RAY  R EY1
+IVY  AY1 V IY0
+MA  M AA1
+OFTEN  AO1 F AH0 N
+OFTEN(1)  AO1 F T AH0 N
+";
+
+    fn fixture() -> (WordList, WordsBTreeMap) {
+        let mut word_list = WordList::from_reader(WORDS.as_bytes(), "words").unwrap();
+        Pronunciation::from_reader(PRONUNCIATIONS.as_bytes(), "pronunciations", Some(&mut word_list), &TableScheme::major(), Variants::All).unwrap();
+        let words = mnemonic::gen_btreemap(&word_list, usize::MAX);
+        (word_list, words)
+    }
+
+    #[test]
+    fn search_matches_propose_mnemonics_path() {
+        let (word_list, words) = fixture();
+        let scorer = WeightedScorer::new();
+        let engine = SearchEngine::new(&word_list, &words, &scorer);
+        let options = SearchOptions { max_words: 5, top_n: 5, ..SearchOptions::new() };
+        for number in ["1", "71", "7121", "1271", "32171", "82", "8121", "0711", "9876", "4530"].iter() {
+            let mnemonic_options = MnemonicOptions { max_words: options.max_words, top_n: options.top_n, ..MnemonicOptions::new(&scorer) };
+            let report = mnemonic::propose_mnemonics_path(&word_list, &words, number, number, &mnemonic_options);
+            let expected = report.best.iter().map(|mnemonic| (mnemonic.score, mnemonic.phrase.clone())).collect::<Vec<_>>();
+            let mut found = engine.search(number, &options).iter().map(|mnemonic| (mnemonic.score, mnemonic.phrase.clone())).collect::<Vec<_>>();
+            found.sort();
+            assert_eq!(found, expected, "best phrases for {}", number);
+            let expected_paths = report.paths.iter().map(|path| path.segments.clone()).collect::<Vec<_>>();
+            assert_eq!(engine.fewest_words_paths(number, &SegmentationPolicy::new(), usize::MAX), expected_paths, "paths for {}", number);
+        }
+    }
+
+    #[test]
+    fn blocked_pairs_still_leave_top_n_phrases() {
+        let (word_list, words) = fixture();
+        let scorer = WeightedScorer::new();
+        let engine = SearchEngine::new(&word_list, &words, &scorer);
+        let number = "7171";
+        // Block every pair in the best five phrases without a filter, which leaves no allowed
+        // phrase made of only the two best words.
+        let mut content_filter = ContentFilter::new(Strictness::Low);
+        for mnemonic in engine.search(number, &SearchOptions::new()) {
+            content_filter.add_pair(&mnemonic.words[0], &mnemonic.words[1], FilterCategory::Slurs);
+        }
+        let options = SearchOptions { top_n: 2, content_filter: Some(content_filter.clone()), ..SearchOptions::new() };
+        let mnemonic_options = MnemonicOptions { top_n: 2, content_filter: Some(&content_filter), ..MnemonicOptions::new(&scorer) };
+        let report = mnemonic::propose_mnemonics_path(&word_list, &words, number, number, &mnemonic_options);
+        assert_eq!(report.best.len(), 2);
+        assert!(report.best.iter().all(|mnemonic| content_filter.allows_phrase(&mnemonic.words)));
+        let expected = report.best.iter().map(|mnemonic| (mnemonic.score, mnemonic.phrase.clone())).collect::<Vec<_>>();
+        let mut found = engine.search(number, &options).iter().map(|mnemonic| (mnemonic.score, mnemonic.phrase.clone())).collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn search_finds_nothing_without_words_for_every_digit() {
+        let (word_list, words) = fixture();
+        let engine = SearchEngine::new(&word_list, &words, &WeightedScorer::new());
+        assert!(engine.search("", &SearchOptions::new()).is_empty());
+        assert!(engine.search("7121", &SearchOptions { max_words: 1, ..SearchOptions::new() }).is_empty());
+    }
+
+    #[test]
+    fn search_long_number_is_fast() {
+        let (word_list, words) = fixture();
+        let engine = SearchEngine::new(&word_list, &words, &WeightedScorer::new());
+        let number = "3217112718121071".repeat(8);
+        assert!(number.len() > 100);
+        let start_time = Instant::now();
+        let mnemonics = engine.search(&number, &SearchOptions::new());
+        let elapsed = Instant::now() - start_time;
+        assert_eq!(mnemonics.len(), 5);
+        assert!(mnemonics.iter().all(|mnemonic| mnemonic.codes.concat() == number));
+        assert!(elapsed < Duration::from_millis(500), "searching {} digits took {:?}", number.len(), elapsed);
+    }
+}
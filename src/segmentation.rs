@@ -0,0 +1,154 @@
+use std::collections::BTreeSet;
+
+/// Rules for how a number can be split into words, on top of which codes have words. By default
+/// any split is allowed and none costs more than another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentationPolicy {
+    // The fewest and most digits one word can cover.
+    pub min_digits: usize,
+    pub max_digits: usize,
+    // If set, every word covers exactly this many digits counting from the front, except the last
+    // one which covers whatever is left. Six gives PAO-style chunks.
+    pub chunk_width: Option<usize>,
+    // Offsets into the digits that every phrase has to split at, like 4 and 6 for YYYYMMDD.
+    pub breaks: BTreeSet<usize>,
+    // Added to a phrase's score for each digit a word's length is away from the target width, so
+    // that 3-3 beats 1-5 for six digits when the words are about as good.
+    pub uneven_penalty: f64,
+    // The width uneven_penalty measures from. If None, the chunk width if there is one, or else
+    // the average width of a split into the fewest possible words.
+    pub target_digits: Option<usize>,
+}
+
+impl SegmentationPolicy {
+    pub fn new() -> Self {
+        Self {
+            min_digits: 1,
+            max_digits: usize::MAX,
+            chunk_width: None,
+            breaks: BTreeSet::new(),
+            uneven_penalty: 0.0,
+            target_digits: None,
+        }
+    }
+
+    /// One word for every width digits.
+    pub fn fixed_width(width: usize) -> Result<Self, String> {
+        if width == 0 {
+            return Err("Expected a chunk width of at least one digit.".to_string());
+        }
+        Ok(Self {
+            chunk_width: Some(width),
+            ..Self::new()
+        })
+    }
+
+    /// Reads break positions written as offsets like "4,6", or as group lengths like "4-2-2".
+    pub fn parse_breaks(text: &str) -> Result<BTreeSet<usize>, String> {
+        let error = || format!("Expected break positions like \"4,6\" or group lengths like \"4-2-2\" but found \"{}\".", text);
+        let numbers = text.split([',', '-'])
+            .map(|number| number.trim().parse::<usize>().map_err(|_| error()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if numbers.contains(&0) {
+            return Err(error());
+        }
+        if text.contains('-') {
+            // Every group but the last ends at a break.
+            Ok(numbers.iter()
+                .take(numbers.len() - 1)
+                .scan(0, |offset, length| {
+                    *offset += length;
+                    Some(*offset)
+                })
+                .collect())
+        } else {
+            Ok(numbers.into_iter().collect())
+        }
+    }
+
+    /// Whether one word can cover the digits from one offset to another in a number with
+    /// digit_count digits.
+    pub fn allows(&self, from: usize, to: usize, digit_count: usize) -> bool {
+        let length = to - from;
+        if let Some(width) = self.chunk_width {
+            if !from.is_multiple_of(width) || length > width || (length < width && to != digit_count) {
+                return false;
+            }
+        }
+        length >= self.min_digits
+            && length <= self.max_digits
+            && self.breaks.range(from + 1..to).next().is_none()
+    }
+
+    /// The width that uneven_penalty measures from, given the fewest words the number can be
+    /// split into.
+    pub fn target_width(&self, digit_count: usize, min_words: usize) -> f64 {
+        match self.target_digits.or(self.chunk_width) {
+            Some(width) => width as f64,
+            None => digit_count as f64 / min_words.max(1) as f64,
+        }
+    }
+
+    /// The cost of one word covering the digits from one offset to another.
+    pub fn penalty(&self, from: usize, to: usize, target_width: f64) -> f64 {
+        if self.uneven_penalty == 0.0 {
+            return 0.0;
+        }
+        self.uneven_penalty * ((to - from) as f64 - target_width).abs()
+    }
+}
+
+impl Default for SegmentationPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use super::SegmentationPolicy;
+
+    fn breaks(offsets: &[usize]) -> BTreeSet<usize> {
+        offsets.iter().copied().collect()
+    }
+
+    #[test]
+    fn parse_breaks_reads_offsets_and_group_lengths() {
+        assert_eq!(SegmentationPolicy::parse_breaks("4,6"), Ok(breaks(&[4, 6])));
+        assert_eq!(SegmentationPolicy::parse_breaks("6, 4"), Ok(breaks(&[4, 6])));
+        assert_eq!(SegmentationPolicy::parse_breaks("4-2-2"), Ok(breaks(&[4, 6])));
+        assert_eq!(SegmentationPolicy::parse_breaks("3"), Ok(breaks(&[3])));
+        assert_eq!(SegmentationPolicy::parse_breaks("10"), Ok(breaks(&[10])));
+        // The last group runs to the end of the number, so it doesn't add a break.
+        assert_eq!(SegmentationPolicy::parse_breaks("2-3-4-5"), Ok(breaks(&[2, 5, 9])));
+    }
+
+    #[test]
+    fn parse_breaks_rejects_bad_input() {
+        for text in ["", "0", "4,0", "4-0-2", "4,,6", "8-", "x", "-4", "4.5"].iter() {
+            assert!(SegmentationPolicy::parse_breaks(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn allows_chunks_and_breaks() {
+        let policy = SegmentationPolicy::fixed_width(3).unwrap();
+        assert!(policy.allows(0, 3, 7));
+        assert!(policy.allows(3, 6, 7));
+        assert!(policy.allows(6, 7, 7));
+        assert!(!policy.allows(0, 2, 7));
+        assert!(!policy.allows(1, 4, 7));
+        let policy = SegmentationPolicy { breaks: breaks(&[4, 6]), ..SegmentationPolicy::new() };
+        assert!(policy.allows(0, 4, 8));
+        assert!(policy.allows(4, 6, 8));
+        assert!(!policy.allows(3, 5, 8));
+        assert!(!policy.allows(0, 8, 8));
+    }
+
+    #[test]
+    fn zero_width_chunks_are_rejected() {
+        assert!(SegmentationPolicy::fixed_width(0).is_err());
+        assert_eq!(SegmentationPolicy::fixed_width(1).unwrap().chunk_width, Some(1));
+    }
+}
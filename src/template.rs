@@ -0,0 +1,93 @@
+use std::fmt;
+
+// The part-of-speech codes used in the frequency list.
+const PART_OF_SPEECH_CODES: [(&str, &str); 14] = [
+    ("a", "article"), ("c", "conjunction"), ("d", "determiner"), ("e", "existential there"),
+    ("i", "preposition"), ("j", "adjective"), ("m", "number"), ("n", "noun"), ("p", "pronoun"),
+    ("r", "adverb"), ("t", "infinitive to"), ("u", "interjection"), ("v", "verb"), ("x", "not"),
+];
+
+// Patterns that tend to make a picture that's easy to remember.
+pub const BUILT_IN_TEMPLATES: [(&str, &str); 6] = [
+    ("adjective-noun", "j n"),
+    ("noun-verb", "n v"),
+    ("adjective-noun-verb", "j n v"),
+    ("noun-verb-noun", "n v n"),
+    ("adjective-noun-verb-noun", "j n v n"),
+    ("adjective-noun-verb-adjective-noun", "j n v j n"),
+];
+
+/// A sequence of part-of-speech slots that a phrase has to fit, one word per slot. A pattern is
+/// written as space-separated slots like "j n v n", where each slot is a part-of-speech code, a
+/// few codes separated by "/" like "j/n" to allow any of them, or "*" for any word.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PosTemplate {
+    // For each slot, the allowed codes. An empty list allows any word.
+    slots: Vec<Vec<String>>,
+}
+
+impl PosTemplate {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let mut slots = vec![];
+        for slot in pattern.split_whitespace() {
+            if slot == "*" {
+                slots.push(vec![]);
+                continue;
+            }
+            let mut codes = vec![];
+            for code in slot.split('/') {
+                let code = code.trim().to_lowercase();
+                if !PART_OF_SPEECH_CODES.iter().any(|(known_code, _)| *known_code == code) {
+                    return Err(format!("Unknown part of speech \"{}\" in template \"{}\". Expected one of {}.",
+                        code, pattern, PART_OF_SPEECH_CODES.iter().map(|(code, name)| format!("{} ({})", code, name)).collect::<Vec<_>>().join(", ")));
+                }
+                codes.push(code);
+            }
+            slots.push(codes);
+        }
+        if slots.is_empty() {
+            return Err("A template needs at least one part of speech.".to_string());
+        }
+        Ok(Self { slots })
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        BUILT_IN_TEMPLATES.iter()
+            .find(|(built_in_name, _)| *built_in_name == name)
+            .map(|(_, pattern)| Self::parse(pattern).unwrap())
+    }
+
+    /// A built-in template by name, or else a pattern like "j n v n".
+    pub fn from_name_or_pattern(name_or_pattern: &str) -> Result<Self, String> {
+        match Self::built_in(name_or_pattern) {
+            Some(template) => Ok(template),
+            None => Self::parse(name_or_pattern),
+        }
+    }
+
+    /// The number of words in a phrase that fits.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Whether a word with this part of speech can go in the slot. Nothing fits past the end.
+    pub fn fits(&self, slot: usize, part_of_speech: &str) -> bool {
+        match self.slots.get(slot) {
+            Some(codes) => codes.is_empty() || codes.iter().any(|code| code.eq_ignore_ascii_case(part_of_speech.trim())),
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for PosTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let slots = self.slots.iter()
+            .map(|codes| if codes.is_empty() { "*".to_string() } else { codes.join("/") })
+            .collect::<Vec<_>>();
+        write!(f, "{}", slots.join(" "))
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use crate::error::LoadError;
+use crate::mnemonic::IndexOptions;
+use crate::encoding::Encoding;
+use crate::words::{Pronunciation, PronunciationMap, Word, WordList};
+
+pub const USER_DICTIONARY_FILE_NAME: &str = "User Dictionary.txt";
+
+// The rank given to words that the user adds unless the file says otherwise. Ranking them with the
+// most common words means max_rank never leaves them out.
+const DEFAULT_USER_RANK: usize = 1;
+
+/// The user's own additions and overrides on top of the word list. The file looks like this:
+///
+/// ```text
+/// # Rank for words added here. Optional.
+/// rank = 1
+///
+/// [words]
+/// # Either ARPAbet phones or one or more codes separated by commas.
+/// kubernetes = K UW2 B ER0 N EH1 T IY0 Z
+/// pi = 9
+///
+/// [persons]
+/// # Names for the person role of a PAO table, written the same way as [words].
+/// Nemo = 23
+///
+/// [pegs]
+/// # The only words to use for a code.
+/// 1 = tie
+/// 2 = Noah
+///
+/// [block]
+/// executive
+/// ```
+///
+/// The rules for merging it with the word list, from strongest to weakest:
+///   1. A blocked word is never used, even if it's one of the user's words or pegs.
+///   2. A code with pegs uses only those words.
+///   3. The codes for one of the user's words replace the ones from the pronunciation dictionary.
+///   4. The user's words and pegs are kept whatever max_rank or min_concreteness say.
+///
+/// Persons are tagged "np" in the word list, which is what makes a word fit the person role.
+#[derive(Clone, Debug, Default)]
+pub struct UserDictionary {
+    pub source_name: String,
+    pub rank: usize,
+    pub words: Vec<UserWord>,
+    // Keyed by code, with the words in the order they were listed.
+    pub pegs: BTreeMap<String, Vec<String>>,
+    // Lowercase.
+    pub blocked: BTreeSet<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UserWord {
+    pub word: String,
+    pub entry: UserWordEntry,
+    // From the [persons] section rather than [words].
+    pub person: bool,
+    // Where the word was in the file, for errors found when applying it.
+    pub line: usize,
+}
+
+#[derive(Clone, Debug)]
+pub enum UserWordEntry {
+    Phones(Vec<String>),
+    Codes(Vec<String>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Section {
+    Top,
+    Words,
+    Persons,
+    Pegs,
+    Block,
+}
+
+impl UserDictionary {
+    pub fn new() -> Self {
+        Self {
+            rank: DEFAULT_USER_RANK,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        let file = File::open(path.as_ref()).map_err(|e| LoadError::io(&file_name, e))?;
+        Self::from_reader(BufReader::new(file), &file_name)
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R, source_name: &str) -> Result<Self, LoadError> {
+        let mut dictionary = Self::new();
+        dictionary.source_name = source_name.to_string();
+        let mut section = Section::Top;
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                section = match line {
+                    "[words]" => Section::Words,
+                    "[persons]" => Section::Persons,
+                    "[pegs]" => Section::Pegs,
+                    "[block]" => Section::Block,
+                    _ => return Err(LoadError::invalid_value(source_name, line_number, "section", line)),
+                };
+                continue;
+            }
+            if section == Section::Block {
+                for word in line.split(',').map(str::trim).filter(|word| !word.is_empty()) {
+                    dictionary.blocked.insert(word.to_lowercase());
+                }
+                continue;
+            }
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| LoadError::missing_field(source_name, line_number, "="))?;
+            let key = key.trim();
+            let value = value.trim().trim_matches('"');
+            if value.is_empty() {
+                return Err(LoadError::missing_field(source_name, line_number, key));
+            }
+            match section {
+                Section::Top => match key {
+                    "rank" => dictionary.rank = value.parse().map_err(|_| LoadError::invalid_value(source_name, line_number, key, value))?,
+                    _ => return Err(LoadError::invalid_value(source_name, line_number, "key", key)),
+                },
+                Section::Words | Section::Persons => {
+                    let entry = if value.chars().all(|c| c.is_ascii_digit() || c == ',' || c.is_whitespace()) {
+                        UserWordEntry::Codes(value.split(',').map(|code| code.trim().to_string()).filter(|code| !code.is_empty()).collect())
+                    } else {
+                        UserWordEntry::Phones(value.split_whitespace().map(str::to_uppercase).collect())
+                    };
+                    dictionary.words.push(UserWord {
+                        word: key.to_string(),
+                        entry,
+                        person: section == Section::Persons,
+                        line: line_number,
+                    });
+                },
+                Section::Pegs => {
+                    if !key.chars().all(|c| c.is_ascii_digit()) {
+                        return Err(LoadError::invalid_value(source_name, line_number, "code", key));
+                    }
+                    let peg_words = dictionary.pegs.entry(key.to_string()).or_default();
+                    peg_words.extend(value.split(',').map(str::trim).filter(|word| !word.is_empty()).map(str::to_string));
+                },
+                Section::Block => unreachable!(),
+            }
+        }
+        Ok(dictionary)
+    }
+
+    /// Adds the user's words and pegs to the word list, replacing the codes of words that are
+    /// already there. Call this after the pronunciations have been loaded so that they don't
+    /// overwrite it. Phones are encoded with the encoding, which for one that works from spelling
+    /// means the word's spelling is used instead.
+    pub fn apply(&self, word_list: &mut WordList, encoding: &dyn Encoding) -> Result<(), LoadError> {
+        for user_word in self.words.iter() {
+            let codes = match &user_word.entry {
+                UserWordEntry::Codes(codes) => codes.clone(),
+                UserWordEntry::Phones(phones) => vec![encoding.encode_word(&user_word.word, phones)
+                    .map_err(|message| LoadError::invalid_value(&self.source_name, user_word.line, &user_word.word, &message))?],
+            };
+            let word = self.entry(word_list, &user_word.word);
+            word.mnemonics = codes.into_iter().filter(|code| !code.is_empty()).collect();
+            word.guessed = false;
+            if user_word.person {
+                word.part_of_speech = "np".to_string();
+            }
+        }
+        for (code, peg_words) in self.pegs.iter() {
+            for peg_word in peg_words.iter() {
+                self.entry(word_list, peg_word).mnemonics.insert(code.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts the user's words and pegs into the pronunciations used for decoding phrases, so that a
+    /// phrase made from them decodes to the same codes apply() gave them. A user word replaces the
+    /// dictionary's pronunciations. Words given as codes get a pronunciation with no phones, one
+    /// per code. A peg word whose pronunciations don't already give its code gets the code as its
+    /// primary pronunciation.
+    pub fn add_to_pronunciations(&self, pronunciations: &mut PronunciationMap, encoding: &dyn Encoding) -> Result<(), LoadError> {
+        for user_word in self.words.iter() {
+            let variants = match &user_word.entry {
+                UserWordEntry::Codes(codes) => codes.iter()
+                    .map(|code| Self::code_pronunciation(&user_word.word, code))
+                    .collect::<Vec<_>>(),
+                UserWordEntry::Phones(phones) => {
+                    let mnemonic = encoding.encode_word(&user_word.word, phones)
+                        .map_err(|message| LoadError::invalid_value(&self.source_name, user_word.line, &user_word.word, &message))?;
+                    vec![Pronunciation {
+                        word: user_word.word.clone(),
+                        variant: 0,
+                        mnemonic,
+                        // Spelling encodings don't read phones, so only the code is kept.
+                        phones: if encoding.phonetic_scheme().is_some() { phones.clone() } else { vec![] },
+                        guessed: false,
+                    }]
+                },
+            };
+            let variants = variants.into_iter().enumerate()
+                .map(|(variant, pronunciation)| Pronunciation { variant, ..pronunciation })
+                .collect();
+            pronunciations.insert(user_word.word.to_lowercase(), variants);
+        }
+        for (code, peg_words) in self.pegs.iter() {
+            for peg_word in peg_words.iter() {
+                let variants = pronunciations.entry(peg_word.to_lowercase()).or_default();
+                if variants.iter().all(|pronunciation| pronunciation.mnemonic != *code) {
+                    variants.insert(0, Self::code_pronunciation(peg_word, code));
+                    for (variant, pronunciation) in variants.iter_mut().enumerate() {
+                        pronunciation.variant = variant;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the blocklist, the pegs and the words to always keep to the options for building the
+    /// index.
+    pub fn add_to_index_options(&self, options: &mut IndexOptions) {
+        options.blocked.extend(self.blocked.iter().cloned());
+        options.always_include.extend(self.words.iter().map(|user_word| user_word.word.to_lowercase()));
+        for (code, peg_words) in self.pegs.iter() {
+            options.always_include.extend(peg_words.iter().map(|peg_word| peg_word.to_lowercase()));
+            options.pegs.entry(code.clone()).or_default().extend(peg_words.iter().map(|peg_word| peg_word.to_lowercase()));
+        }
+    }
+
+    fn code_pronunciation(word: &str, code: &str) -> Pronunciation {
+        Pronunciation {
+            word: word.to_string(),
+            variant: 0,
+            mnemonic: code.to_string(),
+            phones: vec![],
+            guessed: false,
+        }
+    }
+
+    // The word list entry for a word, added with the user rank if it isn't there yet.
+    fn entry<'a>(&self, word_list: &'a mut WordList, word: &str) -> &'a mut Word {
+        word_list.words.entry(word.to_lowercase()).or_insert_with(|| Word {
+            word: word.to_string(),
+            rank: self.rank,
+            frequency: 0,
+            dispersion: 1.0,
+            part_of_speech: "n".to_string(),
+            mnemonics: BTreeSet::new(),
+            guessed: false,
+            concreteness: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::LoadErrorKind;
+    use crate::scheme::TableScheme;
+    use crate::words::{Pronunciation, Variants, WordList};
+    use super::{UserDictionary, UserWordEntry};
+
+    const USER_DICTIONARY: &str = "\
+# Ranked with the most common words.
+rank = 5
+
+[words]
+kubernetes = k uw2 b er0 n eh1 t iy0 z
+pi = 9
+money = 3, 32  # Both.
+
+[persons]
+Nemo = 23
+Tie = 1
+
+[pegs]
+1 = tie
+2 = Noah, net
+
+[block]
+Executive, often
+";
+
+    const WORDS: &str = "\
+Rank\tWord\tPart of speech\tFrequency\tDispersion
+1\tmoney\tn\t900\t0.95
+2\tnet\tn\t800\t0.90
+3\ttie\tv\t600\t0.92
+";
+
+    const PRONUNCIATIONS: &str = "\
+MONEY  M AH1 N IY0
+NET  N EH1 T
+TIE  T AY1
+";
+
+    fn user_dictionary() -> UserDictionary {
+        UserDictionary::from_reader(USER_DICTIONARY.as_bytes(), "user dictionary").unwrap()
+    }
+
+    fn mnemonics(word_list: &WordList, word: &str) -> Vec<String> {
+        word_list.words[word].mnemonics.iter().cloned().collect()
+    }
+
+    #[test]
+    fn from_reader_reads_each_section() {
+        let dictionary = user_dictionary();
+        assert_eq!(dictionary.rank, 5);
+        assert_eq!(dictionary.words.iter().map(|user_word| (user_word.word.as_str(), user_word.line)).collect::<Vec<_>>(),
+            vec![("kubernetes", 5), ("pi", 6), ("money", 7), ("Nemo", 10), ("Tie", 11)]);
+        assert_eq!(dictionary.words.iter().filter(|user_word| user_word.person).count(), 2);
+        assert!(matches!(&dictionary.words[0].entry, UserWordEntry::Phones(phones) if phones[..3] == ["K", "UW2", "B"]));
+        assert!(matches!(&dictionary.words[1].entry, UserWordEntry::Codes(codes) if codes == &["9"]));
+        assert!(matches!(&dictionary.words[2].entry, UserWordEntry::Codes(codes) if codes == &["3", "32"]));
+        assert_eq!(dictionary.pegs["1"], vec!["tie"]);
+        assert_eq!(dictionary.pegs["2"], vec!["Noah", "net"]);
+        assert_eq!(dictionary.blocked.iter().collect::<Vec<_>>(), vec!["executive", "often"]);
+    }
+
+    #[test]
+    fn from_reader_reports_the_line_and_field() {
+        let cases = [
+            ("[words]\npi = 9\n[nouns]\n", 3, "section"),
+            ("[words]\npi 9\n", 2, "="),
+            ("[words]\npi =\n", 2, "pi"),
+            ("ranking = 5\n", 1, "key"),
+            ("rank = five\n", 1, "rank"),
+            ("\n[pegs]\nx1 = tie\n", 3, "code"),
+        ];
+        for (text, line, field) in cases.iter() {
+            let error = UserDictionary::from_reader(text.as_bytes(), "user dictionary").unwrap_err();
+            assert_eq!((error.line, error.field.as_deref()), (Some(*line), Some(*field)), "{:?}", text);
+        }
+        let error = UserDictionary::from_reader("[words]\npi =\n".as_bytes(), "user dictionary").unwrap_err();
+        assert!(matches!(error.kind, LoadErrorKind::MissingField));
+    }
+
+    #[test]
+    fn apply_overrides_existing_words_and_adds_new_ones() {
+        let mut word_list = WordList::from_reader(WORDS.as_bytes(), "words").unwrap();
+        word_list.add_mnemonic("money", "32");
+        word_list.add_mnemonic("net", "21");
+        user_dictionary().apply(&mut word_list, &TableScheme::major()).unwrap();
+
+        // The user's codes replace the dictionary's.
+        assert_eq!(mnemonics(&word_list, "money"), vec!["3", "32"]);
+        assert_eq!(word_list.words["money"].rank, 1);
+        // Phones go through the encoding and new words get the user rank.
+        assert_eq!(mnemonics(&word_list, "kubernetes"), vec!["794210"]);
+        assert_eq!(word_list.words["kubernetes"].rank, 5);
+        assert_eq!(mnemonics(&word_list, "pi"), vec!["9"]);
+        // Pegs add their code to what the word already has.
+        assert_eq!(mnemonics(&word_list, "net"), vec!["2", "21"]);
+        assert_eq!(mnemonics(&word_list, "tie"), vec!["1"]);
+        assert_eq!(mnemonics(&word_list, "noah"), vec!["2"]);
+        assert_eq!(word_list.words["noah"].word, "Noah");
+        assert_eq!(word_list.words["noah"].part_of_speech, "n");
+        // Persons are tagged as proper nouns, even a word that was already listed.
+        assert_eq!(mnemonics(&word_list, "nemo"), vec!["23"]);
+        assert_eq!(word_list.words["nemo"].part_of_speech, "np");
+        assert_eq!(word_list.words["tie"].part_of_speech, "np");
+    }
+
+    #[test]
+    fn apply_reports_phones_the_encoding_rejects() {
+        let dictionary = UserDictionary::from_reader("[words]\nzork = Q X\n".as_bytes(), "user dictionary").unwrap();
+        let mut word_list = WordList::new();
+        let error = dictionary.apply(&mut word_list, &TableScheme::major()).unwrap_err();
+        assert_eq!((error.line, error.field.as_deref()), (Some(2), Some("zork")));
+    }
+
+    #[test]
+    fn add_to_pronunciations_overrides_codes_and_puts_pegs_first() {
+        let pronunciations = Pronunciation::from_reader(PRONUNCIATIONS.as_bytes(), "pronunciations", None, &TableScheme::major(), Variants::All).unwrap();
+        let mut pronunciations = Pronunciation::index(pronunciations);
+        user_dictionary().add_to_pronunciations(&mut pronunciations, &TableScheme::major()).unwrap();
+        let codes = |word: &str| pronunciations[word].iter()
+            .map(|pronunciation| (pronunciation.variant, pronunciation.mnemonic.as_str()))
+            .collect::<Vec<_>>();
+
+        // One pronunciation with no phones per code, replacing the dictionary's.
+        assert_eq!(codes("money"), vec![(0, "3"), (1, "32")]);
+        assert!(pronunciations["money"].iter().all(|pronunciation| pronunciation.phones.is_empty()));
+        assert_eq!(codes("kubernetes"), vec![(0, "794210")]);
+        assert_eq!(pronunciations["kubernetes"][0].phones.len(), 9);
+        // A peg code goes in front unless the word already has it.
+        assert_eq!(codes("tie"), vec![(0, "1")]);
+        assert_eq!(codes("net"), vec![(0, "2"), (1, "21")]);
+        assert_eq!(codes("noah"), vec![(0, "2")]);
+    }
+}
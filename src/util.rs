@@ -0,0 +1,133 @@
+//! Small helpers for parsing, text layout, logging and counting that the rest of the crate shares.
+
+pub mod parse {
+    /// The ASCII digits of the text with everything else removed, like "2068909233" from
+    /// "206-890-9233".
+    pub fn digits_only(text: &str) -> String {
+        text.chars().filter(char::is_ascii_digit).collect()
+    }
+}
+
+pub mod format {
+    /// A blank line and then the label, underlined with "=" at depth 0 and "-" below that and
+    /// indented two spaces per level. The underline is as long as the label but at most width.
+    pub fn header(depth: usize, label: &str, width: usize) -> String {
+        let indent = "  ".repeat(depth);
+        let rule = if depth == 0 { "=" } else { "-" };
+        let rule_length = label.chars().count().min(width.saturating_sub(indent.len())).max(1);
+        format!("\n\n{}{}\n{}{}", indent, label, indent, rule.repeat(rule_length))
+    }
+}
+
+pub mod log {
+    use std::sync::Mutex;
+
+    // Messages for the end of a run, like lines in a data file that couldn't be read.
+    static ENTRIES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    pub fn clear() {
+        ENTRIES.lock().unwrap().clear();
+    }
+
+    pub fn log(entry: &str) {
+        ENTRIES.lock().unwrap().push(entry.to_string());
+    }
+
+    /// Everything logged since the last clear(), oldest first.
+    pub fn get() -> Vec<String> {
+        ENTRIES.lock().unwrap().clone()
+    }
+}
+
+pub mod group {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fmt::Display;
+    use super::format;
+
+    const DISPLAY_WIDTH: usize = 100;
+
+    /// Counts how often each value turns up, for surveying a data file.
+    #[derive(Clone, Debug)]
+    pub struct Grouper<T: Ord> {
+        name: String,
+        counts: BTreeMap<T, usize>,
+    }
+
+    impl<T: Clone + Display + Ord> Grouper<T> {
+        pub fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                counts: BTreeMap::new(),
+            }
+        }
+
+        pub fn record_entry(&mut self, value: &T) {
+            *self.counts.entry(value.clone()).or_insert(0) += 1;
+        }
+
+        /// Prints the values with their counts, most common first, keeping at most max_count of
+        /// them if given.
+        pub fn print_by_count(&self, depth: usize, max_count: Option<usize>) {
+            let mut counts = self.counts.iter().collect::<Vec<_>>();
+            counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            self.print(depth, counts.into_iter().take(max_count.unwrap_or(usize::MAX)));
+        }
+
+        /// Prints the values with their counts in order by value.
+        pub fn list_by_key(&self) {
+            self.print(0, self.counts.iter());
+        }
+
+        fn print<'a>(&'a self, depth: usize, counts: impl Iterator<Item = (&'a T, &'a usize)>) {
+            println!("{}", format::header(depth, &self.name, DISPLAY_WIDTH));
+            let indent = "  ".repeat(depth + 1);
+            for (value, count) in counts {
+                println!("{}{}: {}", indent, value, count);
+            }
+        }
+    }
+
+    pub fn count_distinct<T: Ord>(values: &[T]) -> usize {
+        values.iter().collect::<BTreeSet<_>>().len()
+    }
+
+    /// The values that turn up more than once, each listed once in order.
+    pub fn list_duplicates<T: Clone + Ord>(values: &[T]) -> Vec<T> {
+        let mut seen = BTreeSet::new();
+        let mut duplicates = BTreeSet::new();
+        for value in values.iter() {
+            if !seen.insert(value) {
+                duplicates.insert(value);
+            }
+        }
+        duplicates.into_iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format::header;
+    use super::group::{count_distinct, list_duplicates};
+    use super::parse::digits_only;
+
+    #[test]
+    fn digits_only_drops_everything_else() {
+        assert_eq!(digits_only("+1 (206) 890-9233"), "12068909233");
+        assert_eq!(digits_only("none"), "");
+    }
+
+    #[test]
+    fn header_underlines_the_label() {
+        assert_eq!(header(0, "Best", 100), "\n\nBest\n====");
+        assert_eq!(header(1, "1-2", 100), "\n\n  1-2\n  ---");
+        assert_eq!(header(0, "Coverage", 3), "\n\nCoverage\n===");
+    }
+
+    #[test]
+    fn counts_distinct_and_duplicate_values() {
+        let values = vec!["b", "a", "b", "c", "a", "b"];
+        assert_eq!(count_distinct(&values), 3);
+        assert_eq!(list_duplicates(&values), vec!["a", "b"]);
+        assert!(list_duplicates::<usize>(&[]).is_empty());
+    }
+}
@@ -1,10 +1,22 @@
-use crate::*;
-use util_rust::group::{Grouper, count_distinct, list_duplicates};
-use util_rust::log;
-use std::collections::BTreeMap;
+use crate::error::{DecodeError, LoadError};
+use crate::g2p;
+use crate::ipa;
+use crate::scheme::PhoneticScheme;
+use crate::util::group::{Grouper, count_distinct, list_duplicates};
+use crate::util::log;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
 
-const WORD_FILE_NAME: &str = "English Words Top 5000.txt";
-const PRONUNCIATION_FILE_NAME: &str = "Pronunciations.txt";
+pub const WORD_FILE_NAME: &str = "English Words Top 5000.txt";
+pub const PRONUNCIATION_FILE_NAME: &str = "Pronunciations.txt";
+
+// The rank given to words from the pronunciation dictionary that the frequency list doesn't have,
+// well past the end of the usual list so that they only win when nothing common fits.
+pub const DEFAULT_UNRANKED_RANK: usize = 50_000;
 
 #[derive(Debug)]
 pub struct WordList {
@@ -18,16 +30,69 @@ pub struct Word {
     pub frequency: usize,
     pub dispersion: f64,
     pub part_of_speech: String,
-    pub mnemonic: Option<String>,
+    // One code per distinct pronunciation variant, so a word can match any of them.
+    pub mnemonics: BTreeSet<String>,
+    // True if the word wasn't in the pronunciation dictionary and its codes come from guessing
+    // the pronunciation from the spelling.
+    pub guessed: bool,
+    // How easy the word is to picture, from 0 for the most abstract word in the norms file to 1
+    // for the most concrete. None if the word wasn't rated or no norms were loaded.
+    pub concreteness: Option<f64>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Pronunciation {
+    pub word: String,
+    // 0 for the primary pronunciation, otherwise the number in a line like "DROP(1)".
+    pub variant: usize,
+    pub mnemonic: String,
+    pub phones: Vec<String>,
+    pub guessed: bool,
+}
+
+// One pronunciation read from a dictionary or lexicon line, before it's given its code.
+struct LexiconEntry {
+    line_number: usize,
+    line: String,
     word: String,
-    mnemonic: String,
+    variant: usize,
     phones: Vec<String>,
 }
 
+/// Pronunciations keyed by lowercase word, with the primary pronunciation first.
+pub type PronunciationMap = BTreeMap<String, Vec<Pronunciation>>;
+
+/// The result of turning a phrase back into digits.
+#[derive(Clone, Debug)]
+pub struct PhraseDecoding {
+    pub number: String,
+    pub words: Vec<DecodedWord>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DecodedWord {
+    pub word: String,
+    pub mnemonic: String,
+    /// Each phone of the primary pronunciation with the digits it contributes, if any.
+    pub phones: Vec<(String, String)>,
+    /// Codes from alternate pronunciations that differ from the primary one.
+    pub alternate_mnemonics: Vec<String>,
+    pub guessed: bool,
+}
+
+/// Which pronunciations to use when assigning codes to words.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Variants {
+    All,
+    PrimaryOnly,
+}
+
+impl Default for WordList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl WordList {
     pub fn new() -> Self {
         Self {
@@ -36,123 +101,408 @@ impl WordList {
     }
 
     pub fn fill() -> Self {
+        Self::from_path(WORD_FILE_NAME).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        let file = File::open(path.as_ref()).map_err(|e| LoadError::io(&file_name, e))?;
+        Self::from_reader(BufReader::new(file), &file_name)
+    }
+
+    /// Reads a tab-separated list in the "English Words Top 5000" layout: a header line followed
+    /// by rank, word, part of speech, frequency and dispersion. The source name is only used in
+    /// error messages.
+    pub fn from_reader<R: BufRead>(reader: R, source_name: &str) -> Result<Self, LoadError> {
         let mut words = BTreeMap::new();
-        let lines = util_rust::parse::read_file_as_lines(WORD_FILE_NAME)
-            .iter()
-            .skip(1)
-            .map(|line| line.trim().to_string())
-            .collect::<Vec<_>>();
-        for line in lines.iter() {
-            let splits = line.split("\t").collect::<Vec<_>>();
-            let rank: usize = splits[0].trim().parse().unwrap();
-            let word = splits[1].trim().to_string();
-            let part_of_speech = splits[2].trim().to_string();
-            let frequency: usize = splits[3].trim().parse().unwrap();
-            let dispersion: f64 = splits[4].trim().parse().unwrap();
-            words.insert(word.to_lowercase().to_string(), Word {
+        for (index, line) in reader.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let splits = line.split("\t").map(|split| split.trim()).collect::<Vec<_>>();
+            let field = |field_index: usize, field_name: &str| -> Result<&str, LoadError> {
+                splits.get(field_index)
+                    .copied()
+                    .filter(|value| !value.is_empty())
+                    .ok_or_else(|| LoadError::missing_field(source_name, line_number, field_name))
+            };
+            let rank = parse_field(field(0, "rank")?, source_name, line_number, "rank")?;
+            let word = field(1, "word")?.to_string();
+            let part_of_speech = field(2, "part_of_speech")?.to_string();
+            let frequency = parse_field(field(3, "frequency")?, source_name, line_number, "frequency")?;
+            let dispersion = parse_field(field(4, "dispersion")?, source_name, line_number, "dispersion")?;
+            words.insert(word.to_lowercase(), Word {
                 word,
                 rank,
                 frequency,
                 dispersion,
                 part_of_speech,
-                mnemonic: None,
+                mnemonics: BTreeSet::new(),
+                guessed: false,
+                concreteness: None,
             });
         }
-        Self {
+        Ok(Self {
             words,
+        })
+    }
+
+    pub fn from_counts_path<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        let file = File::open(path.as_ref()).map_err(|e| LoadError::io(&file_name, e))?;
+        Self::from_counts_reader(BufReader::new(file), &file_name)
+    }
+
+    /// Reads a plain frequency list with a word and its count on each line, separated by a tab,
+    /// and optionally a part of speech after another tab. This is the shape of most corpus and
+    /// subtitle frequency lists for other languages. Words are ranked by count, and since there's
+    /// no dispersion every word gets 1. Blank lines and lines starting with "#" are skipped.
+    pub fn from_counts_reader<R: BufRead>(reader: R, source_name: &str) -> Result<Self, LoadError> {
+        let mut counts = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let splits = line.split('\t').map(|split| split.trim()).collect::<Vec<_>>();
+            let count = splits.get(1)
+                .ok_or_else(|| LoadError::missing_field(source_name, line_number, "count"))?;
+            let count: usize = parse_field(count, source_name, line_number, "count")?;
+            let part_of_speech = splits.get(2).copied().unwrap_or("");
+            counts.push((splits[0].to_string(), count, part_of_speech.to_string()));
         }
+        // Stable, so words with the same count keep the file's order.
+        counts.sort_by_key(|(_, count, _)| Reverse(*count));
+        let mut words = BTreeMap::new();
+        for (index, (word, frequency, part_of_speech)) in counts.into_iter().enumerate() {
+            words.entry(word.to_lowercase()).or_insert(Word {
+                word,
+                rank: index + 1,
+                frequency,
+                dispersion: 1.0,
+                part_of_speech,
+                mnemonics: BTreeSet::new(),
+                guessed: false,
+                concreteness: None,
+            });
+        }
+        Ok(Self {
+            words,
+        })
+    }
+
+    pub fn load_concreteness<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        let file = File::open(path.as_ref()).map_err(|e| LoadError::io(&file_name, e))?;
+        self.concreteness_from_reader(BufReader::new(file), &file_name)
+    }
+
+    /// Reads a tab-separated concreteness or imageability norms file with a header line, such as
+    /// the Brysbaert et al. concreteness ratings or the MRC imageability ratings. The word comes
+    /// from the "Word" column and the rating from the first of "Conc.M", "Concreteness",
+    /// "Imageability" or "Rating", falling back to the first and second columns. Ratings are
+    /// rescaled so that the lowest in the file is 0 and the highest is 1, which lets the same
+    /// thresholds work for a 1 to 5 scale and a 100 to 700 one. Returns how many words in the list
+    /// got a rating.
+    pub fn concreteness_from_reader<R: BufRead>(&mut self, reader: R, source_name: &str) -> Result<usize, LoadError> {
+        let mut lines = reader.lines().enumerate();
+        let header = match lines.next() {
+            Some((_, line)) => line.map_err(|e| LoadError::io(source_name, e))?,
+            None => return Ok(0),
+        };
+        let columns = header.split('\t').map(|column| column.trim().to_lowercase()).collect::<Vec<_>>();
+        let word_column = columns.iter().position(|column| column == "word").unwrap_or(0);
+        let rating_column = ["conc.m", "concreteness", "imageability", "rating"].iter()
+            .find_map(|name| columns.iter().position(|column| column == name))
+            .unwrap_or(if word_column == 0 { 1 } else { 0 });
+        let mut ratings = vec![];
+        for (index, line) in lines {
+            let line_number = index + 1;
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let splits = line.split('\t').map(|split| split.trim()).collect::<Vec<_>>();
+            let word = splits.get(word_column).copied().filter(|word| !word.is_empty())
+                .ok_or_else(|| LoadError::missing_field(source_name, line_number, "word"))?;
+            // Some norms leave a rating blank when too few people knew the word.
+            let rating = match splits.get(rating_column).copied().filter(|rating| !rating.is_empty()) {
+                Some(rating) => Some(parse_field::<f64>(rating, source_name, line_number, "concreteness")?)
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| LoadError::invalid_value(source_name, line_number, "concreteness", rating))?,
+                None => continue,
+            };
+            ratings.push((word.to_lowercase(), rating));
+        }
+        let lowest = ratings.iter().map(|(_, rating)| *rating).fold(f64::INFINITY, f64::min);
+        let highest = ratings.iter().map(|(_, rating)| *rating).fold(f64::NEG_INFINITY, f64::max);
+        let mut rated_count = 0;
+        for (word, rating) in ratings.iter() {
+            if let Some(entry) = self.words.get_mut(word) {
+                entry.concreteness = Some(if highest > lowest { (rating - lowest) / (highest - lowest) } else { 1.0 });
+                rated_count += 1;
+            }
+        }
+        Ok(rated_count)
     }
 
-    pub fn fill_with_pronunciation() -> Self {
+    pub fn fill_with_pronunciation(scheme: &dyn PhoneticScheme, variants: Variants) -> Self {
         let mut words = Self::fill();
-        Pronunciation::fill(Some(&mut words));
+        Pronunciation::fill(Some(&mut words), scheme, variants);
         words
     }
 
+    /// Every word in the pronunciation dictionary rather than just the frequency list, with ranks
+    /// from the frequency list if it's there.
+    pub fn fill_all_with_pronunciation(scheme: &dyn PhoneticScheme, variants: Variants, default_rank: usize) -> Self {
+        let pronunciations = Pronunciation::fill(None, scheme, variants);
+        let frequencies = if Path::new(WORD_FILE_NAME).exists() { Some(Self::fill()) } else { None };
+        Self::from_pronunciations(&pronunciations, frequencies.as_ref(), default_rank)
+    }
+
+    /// A word list with every word that has a pronunciation, each with the codes of all its
+    /// pronunciations. Words in the frequency list, which can come from either layout, take their
+    /// rank, frequency, dispersion and part of speech from it. The rest get the default rank, no
+    /// part of speech and a dispersion of 1 as in from_counts_reader(). Entries that aren't plain
+    /// words, like "A." or "!EXCLAMATION-POINT" in the CMU dictionary, are skipped.
+    pub fn from_pronunciations(pronunciations: &[Pronunciation], frequencies: Option<&WordList>, default_rank: usize) -> Self {
+        let mut words = BTreeMap::new();
+        for pronunciation in pronunciations.iter().filter(|pronunciation| !pronunciation.guessed) {
+            let is_word = pronunciation.word.starts_with(char::is_alphabetic)
+                && pronunciation.word.chars().all(|c| c.is_alphabetic() || c == '\'' || c == '-');
+            if !is_word {
+                continue;
+            }
+            let key = pronunciation.word.to_lowercase();
+            let word = words.entry(key.clone()).or_insert_with(|| match frequencies.and_then(|frequencies| frequencies.words.get(&key)) {
+                Some(listed) => Word {
+                    word: listed.word.clone(),
+                    rank: listed.rank,
+                    frequency: listed.frequency,
+                    dispersion: listed.dispersion,
+                    part_of_speech: listed.part_of_speech.clone(),
+                    mnemonics: BTreeSet::new(),
+                    guessed: false,
+                    concreteness: listed.concreteness,
+                },
+                None => Word {
+                    word: key.clone(),
+                    rank: default_rank,
+                    frequency: 0,
+                    dispersion: 1.0,
+                    part_of_speech: "".to_string(),
+                    mnemonics: BTreeSet::new(),
+                    guessed: false,
+                    concreteness: None,
+                },
+            });
+            if !pronunciation.mnemonic.is_empty() {
+                word.mnemonics.insert(pronunciation.mnemonic.clone());
+            }
+        }
+        Self {
+            words,
+        }
+    }
+
     pub fn contains_word(&self, word: &str) -> bool {
         self.words.contains_key(&word.to_lowercase())
     }
 
-    pub fn set_mnemonic(&mut self, word: &str, mnemonic: &str) {
-        let mnemonic = Some(mnemonic.to_string());
+    pub fn add_mnemonic(&mut self, word: &str, mnemonic: &str) {
         let word = self.words.get_mut(&word.to_lowercase());
         if let Some(word) = word {
-            word.mnemonic = mnemonic;
+            word.mnemonics.insert(mnemonic.to_string());
         }
     }
 
 }
 
 impl Word {
+    pub fn has_mnemonic(&self) -> bool {
+        !self.mnemonics.is_empty()
+    }
 }
 
 impl Pronunciation {
-    pub fn fill(mut words: Option<&mut WordList>) -> Vec<Self> {
-        //bg!(&words);
-        let mut v = vec![];
-        let lines = util_rust::parse::read_file_as_lines(PRONUNCIATION_FILE_NAME)
-            .iter()
-            .map(|line| line.trim().to_string())
-            .collect::<Vec<_>>();
-        //ebug_assert!(lines.len() >= 1000);
-        // Ignore the alternate pronunciations with a parenthetical number like:
-        //   DROP     D R AA1 P
-        //   DROP(1)  D R AO1 P
-        // These variations seem to be in vowels and weak sounds like "H", so they won't affect the
-        // mnemonics.
-        for line in lines.iter().filter(|line| !line.contains("(")) {
+    pub fn fill(words: Option<&mut WordList>, scheme: &dyn PhoneticScheme, variants: Variants) -> Vec<Self> {
+        Self::from_path(PRONUNCIATION_FILE_NAME, words, scheme, variants).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P, words: Option<&mut WordList>, scheme: &dyn PhoneticScheme, variants: Variants) -> Result<Vec<Self>, LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        let file = File::open(path.as_ref()).map_err(|e| LoadError::io(&file_name, e))?;
+        Self::from_reader(BufReader::new(file), &file_name, words, scheme, variants)
+    }
+
+    /// Reads a CMU-style dictionary with one word per line followed by its ARPAbet phones. If a
+    /// word list is given, only its words are kept and each one is assigned its codes. Words in
+    /// the list that aren't in the dictionary get a pronunciation guessed from their spelling.
+    pub fn from_reader<R: BufRead>(reader: R, source_name: &str, words: Option<&mut WordList>, scheme: &dyn PhoneticScheme, variants: Variants) -> Result<Vec<Self>, LoadError> {
+        // Alternate pronunciations have a parenthetical number like:
+        //   OFTEN     AO1 F AH0 N
+        //   OFTEN(1)  AO1 F T AH0 N
+        // Some of these differ only in vowels but others add or drop consonants, so unless we've
+        // been asked for the primary pronunciation only, each one gets its own mnemonic.
+        let entries = reader.lines().enumerate().map(|(index, line)| {
+            let line_number = index + 1;
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            let line = line.trim();
+            // Some versions of the CMU dictionary have comment lines starting with ";;;".
+            if line.is_empty() || line.starts_with(";;;") {
+                return Ok(vec![]);
+            }
+            if variants == Variants::PrimaryOnly && line.contains("(") {
+                return Ok(vec![]);
+            }
             let mut splits = line
-                .split(" ")
-                .filter(|&x| !x.is_empty())
-                .map(|split| split.trim().to_string())
+                .split_whitespace()
+                .map(|split| split.to_string())
                 .collect::<Vec<_>>();
-            debug_assert!(splits.len() > 1);
-            let word = splits.remove(0);
-            let use_this_word = words.as_ref().map_or(true, |words| words.contains_word(&word));
-            //bg!(&word, use_this_word);
-            if use_this_word {
-                let phones = splits;
-                match Self::phones_to_mnemonic(&phones) {
+            if splits.len() < 2 {
+                return Err(LoadError::missing_field(source_name, line_number, "phones"));
+            }
+            let (word, variant) = split_variant(&splits.remove(0));
+            Ok(vec![LexiconEntry { line_number, line: line.to_string(), word, variant, phones: splits }])
+        });
+        Self::from_entries(entries, source_name, words, scheme)
+    }
+
+    pub fn from_ipa_path<P: AsRef<Path>>(path: P, words: Option<&mut WordList>, scheme: &dyn PhoneticScheme, variants: Variants) -> Result<Vec<Self>, LoadError> {
+        let file_name = path.as_ref().display().to_string();
+        let file = File::open(path.as_ref()).map_err(|e| LoadError::io(&file_name, e))?;
+        Self::from_ipa_reader(BufReader::new(file), &file_name, words, scheme, variants)
+    }
+
+    /// Reads an IPA lexicon with a word and its transcription on each line, like
+    /// "Straße<tab>/ˈʃtʁaːsə/". Alternate transcriptions follow the first, separated by commas,
+    /// and are numbered like the CMU dictionary's variants. Each is split into phones with
+    /// ipa::tokenize(), so the scheme should be one over IPA phones like major-ipa or major-de.
+    /// Otherwise this works like from_reader().
+    pub fn from_ipa_reader<R: BufRead>(reader: R, source_name: &str, words: Option<&mut WordList>, scheme: &dyn PhoneticScheme, variants: Variants) -> Result<Vec<Self>, LoadError> {
+        let entries = reader.lines().enumerate().map(|(index, line)| {
+            let line_number = index + 1;
+            let line = line.map_err(|e| LoadError::io(source_name, e))?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return Ok(vec![]);
+            }
+            let (word, transcriptions) = line.split_once(|c: char| c.is_whitespace())
+                .ok_or_else(|| LoadError::missing_field(source_name, line_number, "transcription"))?;
+            let transcriptions = transcriptions.split(',').map(str::trim).filter(|transcription| !transcription.is_empty());
+            let take = if variants == Variants::PrimaryOnly { 1 } else { usize::MAX };
+            let entries = transcriptions.take(take).enumerate()
+                .map(|(variant, transcription)| LexiconEntry {
+                    line_number,
+                    line: line.to_string(),
+                    word: word.to_string(),
+                    variant,
+                    phones: ipa::tokenize(transcription),
+                })
+                .collect::<Vec<_>>();
+            if entries.is_empty() || entries.iter().any(|entry| entry.phones.is_empty()) {
+                return Err(LoadError::missing_field(source_name, line_number, "transcription"));
+            }
+            Ok(entries)
+        });
+        Self::from_entries(entries, source_name, words, scheme)
+    }
+
+    fn from_entries<I>(entries: I, source_name: &str, mut words: Option<&mut WordList>, scheme: &dyn PhoneticScheme) -> Result<Vec<Self>, LoadError>
+        where I: Iterator<Item = Result<Vec<LexiconEntry>, LoadError>>
+    {
+        let mut v = vec![];
+        let mut found_words = BTreeSet::new();
+        for entry in entries {
+            for LexiconEntry { line_number, line, word, variant, phones } in entry? {
+                let use_this_word = words.as_ref().is_none_or(|words| words.contains_word(&word));
+                //bg!(&word, use_this_word);
+                if !use_this_word {
+                    continue;
+                }
+                found_words.insert(word.to_lowercase());
+                match Self::phones_to_mnemonic(scheme, &phones) {
                     Ok(mnemonic) => {
-                        if mnemonic.len() > 0 {
-                            words.as_mut().and_then(|words| Some(words.set_mnemonic(&word, &mnemonic)));
-                            // if let Some(ref mut w) = words {
-                            //     w.set_mnemonic(&word, &mnemonic);
-                            //}
-                            v.push(Self {
-                                word,
-                                mnemonic,
-                                phones,
-                            });
+                        // Words like "the" have no digits so they can't be part of a mnemonic,
+                        // but we still keep their pronunciations for decoding phrases.
+                        if !mnemonic.is_empty() {
+                            if let Some(words) = words.as_mut() {
+                                words.add_mnemonic(&word, &mnemonic);
+                            }
                         }
+                        v.push(Self {
+                            word,
+                            variant,
+                            mnemonic,
+                            phones,
+                            guessed: false,
+                        });
                     },
                     Err(message) => {
-                        dbg!(&message);
-                        log::log(&format!("{} in {}", message, line));
+                        // An unknown phone only costs us this one word, so note it and go on.
+                        log::log(&format!("{} in {}, line {}: {}", message, source_name, line_number, line));
                     },
                 };
             }
         }
+        if let Some(words) = words {
+            for word in words.words.values_mut().filter(|word| !found_words.contains(&word.word.to_lowercase())) {
+                if let Some(pronunciation) = Self::guess(&word.word, scheme) {
+                    if !pronunciation.mnemonic.is_empty() {
+                        word.mnemonics.insert(pronunciation.mnemonic.clone());
+                    }
+                    word.guessed = true;
+                    v.push(pronunciation);
+                }
+            }
+        }
         //bg!(v.len());
-        v
-    }
-
-    fn phones_to_mnemonic(phones: &[String]) -> Result<String, String> {
-        debug_assert!(phones.len() > 0);
-        /*
-        Ok(phones
-            .iter()
-            .map(|phone| phone_to_mnemonic_number(phone).ok()?)
-            .map(|opt| opt.map_or("".to_string(), |x| x.to_string()))
-            .collect())
-         */
+        Ok(v)
+    }
+
+    /// A pronunciation guessed from the spelling of a word that isn't in the dictionary.
+    pub fn guess(word: &str, scheme: &dyn PhoneticScheme) -> Option<Self> {
+        let phones = g2p::guess_phones(word);
+        if phones.is_empty() {
+            return None;
+        }
+        let mnemonic = Self::phones_to_mnemonic(scheme, &phones).ok()?;
+        Some(Self {
+            word: word.to_string(),
+            variant: 0,
+            mnemonic,
+            phones,
+            guessed: true,
+        })
+    }
+
+    pub fn index(pronunciations: Vec<Self>) -> PronunciationMap {
+        let mut map = PronunciationMap::new();
+        for pronunciation in pronunciations {
+            map.entry(pronunciation.word.to_lowercase()).or_insert(vec![]).push(pronunciation);
+        }
+        for variants in map.values_mut() {
+            variants.sort_by_key(|pronunciation| pronunciation.variant);
+        }
+        map
+    }
+
+    /// The code for a pronunciation's phones. There has to be at least one phone, since a word with
+    /// none would otherwise look like a word with no digits, like "the".
+    pub fn phones_to_mnemonic(scheme: &dyn PhoneticScheme, phones: &[String]) -> Result<String, String> {
+        if phones.is_empty() {
+            return Err("Expected at least one phone.".to_string());
+        }
         let mut mnemonic = "".to_string();
         for phone in phones.iter() {
-            match phone_to_mnemonic_number(phone) {
-                Ok(opt) => {
-                    if let Some(n) = opt {
-                        mnemonic.push_str(&n.to_string());
-                    }
+            match scheme.phone_to_digits(phone) {
+                Ok(digits) => {
+                    mnemonic.push_str(&digits);
                 },
                 Err(message) => {
                     return Err(message);
@@ -163,15 +513,98 @@ impl Pronunciation {
     }
 }
 
-pub fn survey_words() {
+pub(crate) fn parse_field<T: FromStr>(value: &str, source_name: &str, line_number: usize, field_name: &str) -> Result<T, LoadError> {
+    value.parse().map_err(|_| LoadError::invalid_value(source_name, line_number, field_name, value))
+}
+
+/// Turns a phrase like "executive" back into the number it encodes ("70718"), using the primary
+/// pronunciation of each word. Punctuation other than apostrophes separates words. If
+/// guess_unknown is true, words missing from the dictionary get a pronunciation guessed from their
+/// spelling instead of being reported as errors.
+pub fn decode_phrase(pronunciations: &PronunciationMap, scheme: &dyn PhoneticScheme, phrase: &str, guess_unknown: bool) -> Result<PhraseDecoding, DecodeError> {
+    let tokens = tokenize_phrase(phrase);
+    let mut guesses = PronunciationMap::new();
+    let mut unknown_words = vec![];
+    for token in tokens.iter().filter(|token| !pronunciations.contains_key(&token.to_lowercase())) {
+        match Pronunciation::guess(token, scheme).filter(|_| guess_unknown) {
+            Some(guess) => {
+                guesses.insert(token.to_lowercase(), vec![guess]);
+            },
+            None => unknown_words.push(token.clone()),
+        }
+    }
+    if !unknown_words.is_empty() {
+        return Err(DecodeError::UnknownWords(unknown_words));
+    }
+    let mut number = String::new();
+    let mut words = vec![];
+    for token in tokens {
+        let key = token.to_lowercase();
+        let variants = pronunciations.get(&key).unwrap_or_else(|| &guesses[&key]);
+        let mut phones = vec![];
+        // A user dictionary word given as codes has no phones, just its code.
+        let mut mnemonic = if variants[0].phones.is_empty() { variants[0].mnemonic.clone() } else { String::new() };
+        for phone in variants[0].phones.iter() {
+            let digits = scheme.phone_to_digits(phone)
+                .map_err(|message| DecodeError::Phone { word: token.clone(), message })?;
+            mnemonic.push_str(&digits);
+            phones.push((phone.clone(), digits));
+        }
+        let mut alternate_mnemonics = variants.iter()
+            .skip(1)
+            .filter_map(|pronunciation| if pronunciation.phones.is_empty() {
+                Some(pronunciation.mnemonic.clone())
+            } else {
+                Pronunciation::phones_to_mnemonic(scheme, &pronunciation.phones).ok()
+            })
+            .filter(|alternate| *alternate != mnemonic)
+            .collect::<Vec<_>>();
+        alternate_mnemonics.sort();
+        alternate_mnemonics.dedup();
+        number.push_str(&mnemonic);
+        words.push(DecodedWord {
+            word: token,
+            mnemonic,
+            phones,
+            alternate_mnemonics,
+            guessed: variants[0].guessed,
+        });
+    }
+    Ok(PhraseDecoding {
+        number,
+        words,
+    })
+}
+
+pub(crate) fn tokenize_phrase(phrase: &str) -> Vec<String> {
+    phrase
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .map(|token| token.trim_matches('\''))
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_string())
+        .collect()
+}
+
+// Splits an entry like "DROP(1)" into the word and the variant number.
+fn split_variant(entry: &str) -> (String, usize) {
+    match entry.split_once('(') {
+        Some((word, rest)) => {
+            let variant = rest.trim_end_matches(')').parse().unwrap_or(0);
+            (word.to_string(), variant)
+        },
+        None => (entry.to_string(), 0),
+    }
+}
+
+pub fn survey_words(file_name: &str) -> Result<(), LoadError> {
     let mut ranks = vec![];
     let mut words = vec![];
     let mut part_of_speech_grouper = Grouper::new("Part of Speech");
     let mut frequencies = vec![];
     let mut dispersion_min = f64::MAX;
     let mut dispersion_max = f64::MIN;
-    let lines = util_rust::parse::read_file_as_lines(WORD_FILE_NAME)
-            .iter()
+    let lines = fs::read_to_string(file_name).map_err(|e| LoadError::io(file_name, e))?
+            .lines()
             .skip(1)
             .map(|line| line.trim().to_string())
             .collect::<Vec<_>>();
@@ -201,34 +634,37 @@ pub fn survey_words() {
     part_of_speech_grouper.print_by_count(0, None);
     dbg!(frequencies.len(), count_distinct(&frequencies), frequencies.iter().min(), frequencies.iter().max());
     dbg!(dispersion_min, dispersion_max);
+    Ok(())
 }
 
-pub fn survey_pronunciations() {
+pub fn survey_pronunciations(file_name: &str) -> Result<(), LoadError> {
     let mut words = vec![];
     let mut exception_words = vec![];
     let mut phone_count_grouper = Grouper::new("Phone Counts");
     let mut phone_grouper = Grouper::new("Phones");
-    let lines = util_rust::parse::read_file_as_lines(PRONUNCIATION_FILE_NAME)
-        .iter()
+    let lines = fs::read_to_string(file_name).map_err(|e| LoadError::io(file_name, e))?
+        .lines()
         .map(|line| line.trim().to_string())
         .collect::<Vec<_>>();
     //bg!(&line);
-    // Ignore the alternate pronunciations with a parenthetical number like:
-    //   DROP     D R AA1 P
-    //   DROP(1)  D R AO1 P
-    // These variations seem to be in vowels and weak sounds like "H", so they won't affect the
-    // mnemonics.
-    for line in lines.iter().filter(|line| !line.contains("(")) {
+    // Alternate pronunciations with a parenthetical number like "DROP(1)" are counted separately
+    // so that we can see how many words have more than one.
+    let mut variant_words = vec![];
+    for line in lines.iter() {
         //bg!(&line);
         let splits = line.split(" ").filter(|&x| !x.is_empty()).collect::<Vec<_>>();
         //bg!(&splits);
-        let word = splits[0].trim();
+        let (word, variant) = split_variant(splits[0].trim());
         //bg!(word);
         let first_char: char = word.chars().next().unwrap();
         if !(first_char.is_ascii_uppercase() || first_char.is_ascii_digit()) {
             exception_words.push(word.clone());
         }
-        words.push(word);
+        if variant > 0 {
+            variant_words.push(word);
+        } else {
+            words.push(word);
+        }
         let phone_count = splits.len() - 1;
         // if phone_count >= 18 {
         //     dbg!(&line);
@@ -243,51 +679,85 @@ pub fn survey_pronunciations() {
     phone_grouper.print_by_count(0, None);
     dbg!(&exception_words);
     dbg!(list_duplicates(&words));
+    println!("{} alternate pronunciations for {} words.", variant_words.len(), count_distinct(&variant_words));
+    Ok(())
 }
 
-fn phone_to_mnemonic_number(phone: &str) -> Result<Option<u8>, String> {
-    //bg!(&phone);
-    let phone = if phone.len() == 3 { &phone[..2] } else { phone };
-    match phone {
-        "AA" => Ok(None),
-        "AE" => Ok(None),
-        "AH" => Ok(None),
-        "AO" => Ok(None),
-        "AW" => Ok(None),
-        "AY" => Ok(None),
-        "B" => Ok(Some(9)),
-        "CH" => Ok(Some(6)),
-        "D" => Ok(Some(1)),
-        "DH" => Ok(None),
-        "EH" => Ok(None),
-        "ER" => Ok(Some(4)),
-        "EY" => Ok(None),
-        "F" => Ok(Some(8)),
-        "G" => Ok(Some(7)),
-        "HH" => Ok(None),
-        "IH" => Ok(None),
-        "IY" => Ok(None),
-        "JH" => Ok(Some(6)),
-        "K" => Ok(Some(7)),
-        "L" => Ok(Some(5)),
-        "M" => Ok(Some(3)),
-        "N" => Ok(Some(2)),
-        "NG" => Ok(Some(2)),
-        "OW" => Ok(None),
-        "OY" => Ok(None),
-        "P" => Ok(Some(9)),
-        "R" => Ok(Some(4)),
-        "S" => Ok(Some(0)),
-        "SH" => Ok(Some(6)),
-        "T" => Ok(Some(1)),
-        "TH" => Ok(Some(1)),
-        "UH" => Ok(None),
-        "UW" => Ok(None),
-        "V" => Ok(Some(8)),
-        "W" => Ok(None),
-        "Y" => Ok(None),
-        "Z" => Ok(Some(0)),
-        "ZH" => Ok(Some(6)),
-        _ => Err(format!("Unexpected phone = {}", phone)),
+#[cfg(test)]
+mod tests {
+    use crate::error::DecodeError;
+    use crate::scheme::TableScheme;
+    use super::{decode_phrase, split_variant, tokenize_phrase, Pronunciation, PronunciationMap, Variants};
+
+    const PRONUNCIATIONS: &str = "\
+EXECUTIVE  IH0 G Z EH1 K Y AH0 T IH0 V
+MONEY  M AH1 N IY0
+O'CLOCK  AH0 K L AA1 K
+OFTEN  AO1 F AH0 N
+OFTEN(1)  AO1 F T AH0 N
+THE  DH AH0
+";
+
+    fn pronunciations() -> PronunciationMap {
+        let pronunciations = Pronunciation::from_reader(PRONUNCIATIONS.as_bytes(), "pronunciations", None, &TableScheme::major(), Variants::All).unwrap();
+        Pronunciation::index(pronunciations)
+    }
+
+    #[test]
+    fn tokenize_phrase_splits_on_punctuation_but_keeps_apostrophes() {
+        assert_eq!(tokenize_phrase("Executive, money!"), vec!["Executive", "money"]);
+        assert_eq!(tokenize_phrase("rock-n-roll"), vec!["rock", "n", "roll"]);
+        assert_eq!(tokenize_phrase("'twas o'clock'"), vec!["twas", "o'clock"]);
+        assert!(tokenize_phrase(" ... ").is_empty());
+    }
+
+    #[test]
+    fn phones_to_mnemonic_needs_phones() {
+        let phones = |text: &str| text.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(Pronunciation::phones_to_mnemonic(&TableScheme::major(), &phones("M AH1 N IY0")), Ok("32".to_string()));
+        assert_eq!(Pronunciation::phones_to_mnemonic(&TableScheme::major(), &phones("DH AH0")), Ok("".to_string()));
+        assert!(Pronunciation::phones_to_mnemonic(&TableScheme::major(), &[]).is_err());
+    }
+
+    #[test]
+    fn split_variant_reads_the_variant_number() {
+        assert_eq!(split_variant("DROP"), ("DROP".to_string(), 0));
+        assert_eq!(split_variant("DROP(1)"), ("DROP".to_string(), 1));
+        assert_eq!(split_variant("DROP(12)"), ("DROP".to_string(), 12));
+        assert_eq!(split_variant("DROP(x)"), ("DROP".to_string(), 0));
+    }
+
+    #[test]
+    fn decode_phrase_joins_the_words_codes() {
+        let decoding = decode_phrase(&pronunciations(), &TableScheme::major(), "The executive's... no, THE Executive, money!", false);
+        assert!(matches!(decoding, Err(DecodeError::UnknownWords(ref words)) if words == &["executive's", "no"]));
+        let decoding = decode_phrase(&pronunciations(), &TableScheme::major(), "The Executive, money! At o'clock", false);
+        assert!(matches!(decoding, Err(DecodeError::UnknownWords(ref words)) if words == &["At"]));
+        let decoding = decode_phrase(&pronunciations(), &TableScheme::major(), "The Executive, money; o'clock.", false).unwrap();
+        assert_eq!(decoding.number, "7071832757");
+        assert_eq!(decoding.words.iter().map(|word| word.mnemonic.as_str()).collect::<Vec<_>>(), vec!["", "70718", "32", "757"]);
+        assert_eq!(decoding.words[2].phones, vec![
+            ("M".to_string(), "3".to_string()),
+            ("AH1".to_string(), "".to_string()),
+            ("N".to_string(), "2".to_string()),
+            ("IY0".to_string(), "".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn decode_phrase_lists_alternate_codes() {
+        let decoding = decode_phrase(&pronunciations(), &TableScheme::major(), "often", false).unwrap();
+        assert_eq!(decoding.number, "82");
+        assert_eq!(decoding.words[0].alternate_mnemonics, vec!["812"]);
+    }
+
+    #[test]
+    fn decode_phrase_guesses_unknown_words_only_when_asked() {
+        let decoding = decode_phrase(&pronunciations(), &TableScheme::major(), "money tom", false);
+        assert!(matches!(decoding, Err(DecodeError::UnknownWords(ref words)) if words == &["tom"]));
+        let decoding = decode_phrase(&pronunciations(), &TableScheme::major(), "money tom", true).unwrap();
+        assert_eq!(decoding.number, "3213");
+        assert!(!decoding.words[0].guessed);
+        assert!(decoding.words[1].guessed);
     }
 }